@@ -6,6 +6,7 @@
 //!
 
 use proc_macro2::Span;
+use protocol::schema::Schemify;
 use util::Rustify;
 mod protocol;
 mod util;
@@ -19,7 +20,7 @@ const SOURCES: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/SOURCES
 ///
 /// Fetch the protocols, either from locally or the GitHub repo.
 ///
-fn fetch_protocols() -> [String; 2] {
+pub fn fetch_protocols() -> [String; 2] {
     #[allow(unused_mut)]
     let mut sources = [
         include_str!("../test/protocol.json").to_string(),
@@ -50,17 +51,27 @@ fn fetch_protocols() -> [String; 2] {
 }
 
 ///
-/// Parses and rustifies the protcols.
+/// Parses the protocols.
+///
+fn parse_protocols(
+    sources: impl IntoIterator<Item = String>,
+) -> Result<Vec<crate::protocol::Protocol>, serde_json::Error> {
+    sources
+        .into_iter()
+        .map(|src| serde_json::from_str::<crate::protocol::Protocol>(&src))
+        .collect()
+}
+
+///
+/// Rustifies the protcols.
 ///
 fn protocols_to_rust(
     span: impl Into<Span>,
-    sources: impl IntoIterator<Item = String>,
+    protocols: impl IntoIterator<Item = crate::protocol::Protocol>,
 ) -> syn::File {
     let span = span.into();
-    let mut protocols = sources
+    let mut protocols = protocols
         .into_iter()
-        .map(|src| serde_json::from_str::<crate::protocol::Protocol>(&src))
-        .map(|protocol| protocol.expect("Error parsing protocol"))
         .map(|prototcol| prototcol.rustify(span, None))
         .collect::<Vec<_>>();
 
@@ -74,10 +85,170 @@ fn protocols_to_rust(
 }
 
 ///
-/// Returns the source code for a binding file.
+/// Parses the protocols, and merges their definitions
+/// into a single JSON Schema document.
 ///
-pub fn generate_protocol_bindings() -> String {
-    let protocols = fetch_protocols();
-    let file = protocols_to_rust(Span::call_site(), protocols);
+fn protocols_to_json_schema(sources: impl IntoIterator<Item = String>) -> serde_json::Value {
+    let mut schemas = parse_protocols(sources)
+        .expect("Error parsing protocol")
+        .into_iter()
+        .map(|protocol| protocol.schemify(None))
+        .collect::<Vec<_>>();
+
+    let mut first = schemas.remove(0);
+
+    for mut schema in schemas {
+        if let (Some(defs), Some(more)) = (
+            first["$defs"].as_object_mut(),
+            schema["$defs"].as_object_mut(),
+        ) {
+            defs.append(more);
+        }
+    }
+
+    first
+}
+
+///
+/// Returns a JSON Schema (draft 2020-12) document for the protocols `sources`,
+/// with a definition for every type, and every command's parameters,
+/// return value and event payload.
+///
+pub fn generate_json_schema(sources: &[String]) -> String {
+    let schema = protocols_to_json_schema(sources.to_vec());
+    serde_json::to_string_pretty(&schema).expect("Could not serialize JSON Schema")
+}
+
+///
+/// Returns the protocols `sources` merged into a single definition,
+/// as served by browsers on `/json/protocol`:
+/// the first protocol's `version`, and the `domains` of all of them.
+///
+pub fn generate_protocol_json(sources: &[String]) -> String {
+    let mut protocols = sources
+        .iter()
+        .map(|src| serde_json::from_str::<serde_json::Value>(src))
+        .collect::<Result<Vec<_>, _>>()
+        .expect("Error parsing protocol");
+
+    let mut first = protocols.remove(0);
+
+    for mut protocol in protocols {
+        if let (Some(domains), Some(more)) = (
+            first["domains"].as_array_mut(),
+            protocol["domains"].as_array_mut(),
+        ) {
+            domains.append(more);
+        }
+    }
+
+    serde_json::to_string(&first).expect("Could not serialize protocol")
+}
+
+///
+/// Returns the source code for a binding file of the protocols `sources`.
+///
+pub fn generate_protocol_bindings(sources: &[String]) -> String {
+    let file = generate(Options::new(sources.to_vec())).expect("Error generating bindings");
     prettyplease::unparse(&file)
 }
+
+///
+/// Errors encountered while generating bindings.
+///
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    ///
+    /// A protocol definition is not valid.
+    ///
+    #[error("Error parsing protocol: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    ///
+    /// A requested domain is not in any of the protocols.
+    ///
+    #[error("Unknown domain `{0}`")]
+    UnknownDomain(String),
+
+    ///
+    /// A generated domain depends on a domain which is not in any of the protocols.
+    ///
+    #[error("Domain `{domain}` depends on `{dependency}`, which is not in any of the protocols")]
+    MissingDependency { domain: String, dependency: String },
+}
+
+///
+/// Options for generating bindings with [generate].
+///
+#[derive(Clone)]
+pub struct Options {
+    ///
+    /// Contents of the protocol definition files (`protocol.json`).
+    ///
+    /// Defaults to the vendored (or `latest`) protocols.
+    ///
+    pub sources: Vec<String>,
+
+    ///
+    /// Only generate these domains (by their original name, e.g. `Page`),
+    /// along with every domain they depend on.
+    ///
+    /// All domains are generated if `None`.
+    ///
+    pub domains: Option<Vec<String>>,
+
+    ///
+    /// Path to the `chrome_devtools_api` crate, which generated
+    /// items use for their traits (e.g. `crate::util::Command`).
+    ///
+    pub root: syn::Path,
+
+    ///
+    /// Span given to all the generated items.
+    ///
+    pub span: Span,
+}
+
+impl Options {
+    ///
+    /// Options generating every domain of the protocols `sources`.
+    ///
+    pub fn new(sources: Vec<String>) -> Self {
+        Self {
+            sources,
+            domains: None,
+            root: util::rust::crate_(Span::call_site()).into(),
+            span: Span::call_site(),
+        }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::new(fetch_protocols().to_vec())
+    }
+}
+
+///
+/// Generates the bindings' AST, with the provided [Options].
+///
+pub fn generate(options: Options) -> Result<syn::File, Error> {
+    let mut protocols = parse_protocols(options.sources)?;
+
+    if let Some(ref domains) = options.domains {
+        protocol::retain_domains(&mut protocols, domains)?;
+    }
+
+    let method_call = protocol::dispatch::method_call(options.span, &protocols);
+    let any_event = protocol::dispatch::any_event(options.span, &protocols);
+    let domain_clients = protocol::facade::domain_clients(options.span, &protocols);
+
+    let mut file = protocols_to_rust(options.span, protocols);
+    file.items.extend(method_call);
+    file.items.extend(any_event);
+    file.items.extend(domain_clients);
+
+    protocol::post_ast::reroot(&mut file, &options.root);
+
+    Ok(file)
+}
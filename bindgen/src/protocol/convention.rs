@@ -15,14 +15,14 @@ use convert_case::{Case, Casing};
 pub trait NamingConvention: std::fmt::Debug + Copy + Clone {
     const CASE: Case;
 
-    fn convert(src: String) -> String 
-        where Self: Sized
+    fn convert(src: String) -> String
+    where
+        Self: Sized,
     {
         src.to_case(Self::CASE)
     }
 }
 
-
 ///
 /// Naming convention for a Domain (a `mod` in Rust).
 ///
@@ -77,3 +77,74 @@ impl NamingConvention for Event {
         format!("{}Event", src.to_case(Self::CASE))
     }
 }
+
+///
+/// Naming convention for a domain's marker type
+/// (a unit `struct` in Rust, with "Domain" added to the end)
+///
+#[derive(Debug, Clone, Copy)]
+pub struct DomainMarker;
+
+impl NamingConvention for DomainMarker {
+    const CASE: Case = Case::Pascal;
+
+    fn convert(src: String) -> String {
+        format!("{}Domain", src.to_case(Self::CASE))
+    }
+}
+
+///
+/// Naming convention for a command's method
+/// on its domain's client facade (`fn` in Rust).
+///
+#[derive(Debug, Clone, Copy)]
+pub struct Method;
+
+impl NamingConvention for Method {
+    const CASE: Case = Case::Snake;
+}
+
+///
+/// Naming convention for a domain's client facade
+/// (a `struct` in Rust, with "Client" added to the end)
+///
+#[derive(Debug, Clone, Copy)]
+pub struct DomainClient;
+
+impl NamingConvention for DomainClient {
+    const CASE: Case = Case::Pascal;
+
+    fn convert(src: String) -> String {
+        format!("{}Client", src.to_case(Self::CASE))
+    }
+}
+
+///
+/// Naming convention for a domain's server-side handler
+/// (a `trait` in Rust, with "Handler" added to the end)
+///
+#[derive(Debug, Clone, Copy)]
+pub struct DomainHandler;
+
+impl NamingConvention for DomainHandler {
+    const CASE: Case = Case::Pascal;
+
+    fn convert(src: String) -> String {
+        format!("{}Handler", src.to_case(Self::CASE))
+    }
+}
+
+///
+/// Naming convention for a command's call builder
+/// (a `struct` in Rust, with "Call" added to the end)
+///
+#[derive(Debug, Clone, Copy)]
+pub struct CommandCall;
+
+impl NamingConvention for CommandCall {
+    const CASE: Case = Case::Pascal;
+
+    fn convert(src: String) -> String {
+        format!("{}Call", src.to_case(Self::CASE))
+    }
+}
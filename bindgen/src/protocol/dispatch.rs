@@ -0,0 +1,246 @@
+//!
+//! Protocol-wide items, which cover the commands
+//! and events of every domain at once:
+//! * `MethodCall`: enum of every command's parameters.
+//! * `AnyEvent`: enum of every event.
+//!
+
+use proc_macro2::Span;
+use syn::parse_quote_spanned;
+
+use super::{
+    convention as conv,
+    modular::{self as m, Identifier},
+    Domain, Protocol,
+};
+use crate::util::{self, Rustify};
+
+///
+/// A variant of a method-tagged enum.
+///
+struct Variant {
+    ///
+    /// Wire name (`Domain.method`).
+    ///
+    id: String,
+
+    ///
+    /// Variant name (`Domain_Method`).
+    ///
+    ident: syn::Ident,
+
+    ///
+    /// Path to the variant's inner type.
+    ///
+    ty: syn::Path,
+}
+
+impl Domain {
+    ///
+    /// `(module, Pascal-cased name)` of this domain.
+    ///
+    fn idents(&self, span: Span) -> (syn::Ident, String) {
+        (
+            self.domain.clone().rustify(span, None),
+            m::NamedIdentifier::<conv::Type>::new(self.domain.original()).to_string(),
+        )
+    }
+}
+
+impl Protocol {
+    fn command_variants(&self, span: Span) -> impl Iterator<Item = Variant> + '_ {
+        self.domains.iter().flat_map(move |d| {
+            let (module, domain) = d.idents(span);
+
+            d.commands.iter().flatten().map(move |c| {
+                let ident = c.name.clone().rustify(span, None);
+
+                let ty = match c.parameters {
+                    Some(_) => {
+                        let params = syn::Ident::new(&format!("{ident}Params"), span);
+                        parse_quote_spanned!(span=> #module::#params)
+                    }
+                    None => util::rust::Nothing(span),
+                };
+
+                Variant {
+                    id: format!("{}.{}", d.domain.original(), c.name.original()),
+                    ident: syn::Ident::new(&format!("{domain}_{ident}"), span),
+                    ty,
+                }
+            })
+        })
+    }
+
+    fn event_variants(&self, span: Span) -> impl Iterator<Item = Variant> + '_ {
+        self.domains.iter().flat_map(move |d| {
+            let (module, domain) = d.idents(span);
+
+            d.events.iter().flatten().map(move |e| {
+                let ident = e.name.clone().rustify(span, None);
+                let name = m::NamedIdentifier::<conv::Command>::new(e.name.original());
+
+                Variant {
+                    id: format!("{}.{}", d.domain.original(), e.name.original()),
+                    ident: syn::Ident::new(&format!("{domain}_{}", name.to_string()), span),
+                    ty: parse_quote_spanned!(span=> #module::#ident),
+                }
+            })
+        })
+    }
+}
+
+///
+/// Generates an enum tagged by method name, which implements `Method`
+/// and (de)serializes as `{ "method": "...", "params": { ... } }`.
+///
+/// If `fallback` is set, unknown methods are kept in
+/// an `Unknown { method, params }` variant, instead of erroring.
+///
+fn method_enum(
+    span: Span,
+    ident: syn::Ident,
+    docs: Vec<syn::Attribute>,
+    variants: Vec<Variant>,
+    fallback: bool,
+) -> Vec<syn::Item> {
+    let method = util::rust::Method(span);
+    let serialize_method = util::rust::serialize_method(span);
+    let deserialize_method = util::rust::deserialize_method(span);
+
+    let definitions = variants.iter().map(|v| {
+        let (variant, ty, doc) = (&v.ident, &v.ty, format!(" `{}`", v.id));
+        quote::quote_spanned!(span=> #[doc = #doc] #variant(#ty))
+    });
+
+    let method_arms = variants.iter().map(|v| {
+        let (variant, id) = (&v.ident, &v.id);
+        quote::quote_spanned!(span=> Self::#variant(_) => #id)
+    });
+
+    let from_arms = variants.iter().map(|v| {
+        let (variant, id) = (&v.ident, &v.id);
+        quote::quote_spanned!(span=> #id => serde_json::from_value(params).map(Self::#variant))
+    });
+
+    let serialize_arms = variants.iter().map(|v| {
+        let variant = &v.ident;
+        quote::quote_spanned!(span=>
+            Self::#variant(params) => #serialize_method(serializer, #method::method(self), params)
+        )
+    });
+
+    let (unknown, unknown_method, unknown_from, unknown_serialize) = match fallback {
+        true => (
+            quote::quote_spanned!(span=>
+                /// Any other method, with its raw parameters.
+                Unknown { method: String, params: serde_json::Value },
+            ),
+            quote::quote_spanned!(span=> Self::Unknown { method, .. } => method,),
+            quote::quote_spanned!(span=> _ => Ok(Self::Unknown { method: method.to_string(), params }),),
+            quote::quote_spanned!(span=>
+                Self::Unknown { method, params } => #serialize_method(serializer, method, params),
+            ),
+        ),
+        false => (
+            Default::default(),
+            Default::default(),
+            quote::quote_spanned!(span=>
+                _ => Err(serde::de::Error::custom(format!("Unknown method `{method}`"))),
+            ),
+            Default::default(),
+        ),
+    };
+
+    vec![
+        parse_quote_spanned! {span=>
+            #(#docs)*
+            #[allow(non_camel_case_types)]
+            #[derive(Debug, Clone)]
+            pub enum #ident {
+                #(#definitions,)*
+                #unknown
+            }
+        },
+        parse_quote_spanned! {span=>
+            impl #method for #ident {
+                fn method(&self) -> &str {
+                    match self {
+                        #(#method_arms,)*
+                        #unknown_method
+                    }
+                }
+
+                fn from_method(method: &str, params: serde_json::Value) -> Result<Self, serde_json::Error> {
+                    match method {
+                        #(#from_arms,)*
+                        #unknown_from
+                    }
+                }
+            }
+        },
+        parse_quote_spanned! {span=>
+            impl serde::Serialize for #ident {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: serde::Serializer,
+                {
+                    match self {
+                        #(#serialize_arms,)*
+                        #unknown_serialize
+                    }
+                }
+            }
+        },
+        parse_quote_spanned! {span=>
+            impl<'de> serde::Deserialize<'de> for #ident {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: serde::Deserializer<'de>,
+                {
+                    #deserialize_method(deserializer)
+                }
+            }
+        },
+    ]
+}
+
+///
+/// Generates the `MethodCall` enum, with a variant for every command's parameters.
+///
+pub fn method_call(span: Span, protocols: &[Protocol]) -> Vec<syn::Item> {
+    let variants = protocols
+        .iter()
+        .flat_map(|p| p.command_variants(span))
+        .collect();
+
+    let docs = util::rust::rustdoc(
+        "Parameters of any command, tagged by the command's method name.\n\n\
+        (De)serializes as `{ \"method\": \"...\", \"params\": { ... } }`.",
+        span,
+    )
+    .collect();
+
+    method_enum(span, syn::Ident::new("MethodCall", span), docs, variants, false)
+}
+
+///
+/// Generates the `AnyEvent` enum, with a variant for every event,
+/// and a fallback for unknown events.
+///
+pub fn any_event(span: Span, protocols: &[Protocol]) -> Vec<syn::Item> {
+    let variants = protocols
+        .iter()
+        .flat_map(|p| p.event_variants(span))
+        .collect();
+
+    let docs = util::rust::rustdoc(
+        "Any event, tagged by the event's method name.\n\n\
+        (De)serializes as `{ \"method\": \"...\", \"params\": { ... } }`,\n\
+        with unknown events kept in [AnyEvent::Unknown].",
+        span,
+    )
+    .collect();
+
+    method_enum(span, syn::Ident::new("AnyEvent", span), docs, variants, true)
+}
@@ -0,0 +1,312 @@
+//!
+//! Client facades, calling a domain's commands through any `Caller`:
+//! * `{Domain}Client`: a method per command of the domain.
+//! * `{Command}Call`: what each method returns, which makes the call once awaited.
+//!   Required parameters are arguments of the method, and optional
+//!   parameters are setters of the call.
+//! * `DomainClients`: a method per domain, returning its client,
+//!   implemented for every `Caller`.
+//!
+
+use proc_macro2::Span;
+use quote::quote_spanned;
+use syn::parse_quote_spanned;
+
+use super::{
+    convention as conv,
+    modular::{self as m, Identifier},
+    rustify::deprecated_docs_experimental,
+    Command, Domain, Field, Protocol,
+};
+use crate::util::{self, Contextual, Rustify, ToPath};
+
+///
+/// Above this many required parameters,
+/// methods trip `clippy::too_many_arguments`.
+///
+const MAX_ARGUMENTS: usize = 7;
+
+///
+/// A command's parameter, as an argument of its method or a setter of its call.
+///
+struct Parameter {
+    ident: syn::Ident,
+
+    ///
+    /// Type of the parameter, without the `Option` of optional ones.
+    ///
+    ty: syn::Type,
+
+    ///
+    /// Documentation (and deprecation) of the parameter.
+    ///
+    attrs: Vec<syn::Attribute>,
+
+    optional: bool,
+}
+
+///
+/// Inner type of an `Option<T>`.
+///
+fn option_inner(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+
+    let segment = path.path.segments.last().filter(|s| s.ident == "Option")?;
+
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+
+    match args.args.first()? {
+        syn::GenericArgument::Type(inner) => Some(inner),
+        _ => None,
+    }
+}
+
+impl Field {
+    ///
+    /// This field as a parameter, with the context of its command,
+    /// so it names the same types as the command's parameter struct.
+    ///
+    fn parameter(&self, span: Span, ctx: Option<util::Context>) -> Parameter {
+        let optional = self.ty.is_optional();
+        let (field, _) = self.clone().rustify(span, ctx);
+
+        let ty = match optional {
+            true => option_inner(&field.ty).cloned().unwrap_or(field.ty),
+            false => field.ty,
+        };
+
+        let attrs = field
+            .attrs
+            .into_iter()
+            .filter(|attr| !attr.path().is_ident("serde"))
+            .collect();
+
+        Parameter {
+            ident: field.ident.expect("Parameters are named fields"),
+            ty,
+            attrs,
+            optional,
+        }
+    }
+}
+
+impl Command {
+    ///
+    /// Generates the command's method of its domain's client,
+    /// and the call it returns.
+    ///
+    fn gen_facade(
+        &self,
+        span: Span,
+        ctx: Option<util::Context>,
+    ) -> (proc_macro2::TokenStream, Vec<syn::Item>) {
+        let ctx = ctx.next(self.name.clone());
+
+        let command = self.name.clone().rustify(span, ctx.clone());
+        let method =
+            m::NamedIdentifier::<conv::Method>::new(self.name.original()).rustify(span, None);
+        let call =
+            m::NamedIdentifier::<conv::CommandCall>::new(self.name.original()).rustify(span, None);
+
+        let caller = util::rust::Caller(span);
+        let call_future = util::rust::CallFuture(span);
+
+        let [params, returns] = [(&self.parameters, "Params"), (&self.returns, "Returns")].map(
+            |(fields, suffix)| -> syn::Path {
+                match fields {
+                    Some(_) => [format!("{command}{suffix}")]
+                        .map(util::to_ident(span))
+                        .to_path(),
+                    None => util::rust::Nothing(span),
+                }
+            },
+        );
+
+        let (optional, required): (Vec<_>, Vec<_>) = self
+            .parameters
+            .iter()
+            .flatten()
+            .map(|f| f.parameter(span, ctx.clone()))
+            .partition(|p| p.optional);
+
+        let args = required
+            .iter()
+            .map(|Parameter { ident, ty, .. }| quote_spanned!(span=> #ident: impl Into<#ty>));
+
+        let params_expr = match (required.is_empty(), optional.is_empty()) {
+            (true, _) => quote_spanned!(span=> Default::default()),
+            (false, all_required) => {
+                let fields = required.iter().map(|p| &p.ident);
+                let rest = (!all_required).then(|| quote_spanned!(span=> ..Default::default()));
+
+                quote_spanned!(span=> #params { #(#fields: #fields.into(),)* #rest })
+            }
+        };
+
+        let setters = optional.iter().map(
+            |Parameter {
+                 ident, ty, attrs, ..
+             }| {
+                quote_spanned! {span=>
+                    #(#attrs)*
+                    pub fn #ident(mut self, #ident: impl Into<#ty>) -> Self {
+                        self.params.#ident = Some(#ident.into());
+                        self
+                    }
+                }
+            },
+        );
+
+        let docs = deprecated_docs_experimental(
+            ctx.clone(),
+            span,
+            self.deprecated,
+            self.description.clone(),
+            self.experimental,
+        );
+
+        let too_many = (required.len() > MAX_ARGUMENTS)
+            .then(|| quote_spanned!(span=> #[allow(clippy::too_many_arguments)]));
+
+        let method = quote_spanned! {span=>
+            #(#docs)*
+            #too_many
+            pub fn #method(&self, #(#args),*) -> #call<'a, C> {
+                #call {
+                    caller: self.caller,
+                    params: #params_expr,
+                }
+            }
+        };
+
+        let call_docs = format!("Call of [{command}], made once awaited.");
+        let call_docs = util::rust::rustdoc(&call_docs, span);
+
+        let items = vec![
+            parse_quote_spanned! {span=>
+                #(#call_docs)*
+                #[must_use = "calls are only made once awaited"]
+                pub struct #call<'a, C> {
+                    caller: &'a C,
+                    params: #params,
+                }
+            },
+            parse_quote_spanned! {span=>
+                #[allow(deprecated)]
+                impl<'a, C: #caller> #call<'a, C> {
+                    #(#setters)*
+                }
+            },
+            parse_quote_spanned! {span=>
+                #[allow(deprecated)]
+                impl<'a, C: #caller> ::std::future::IntoFuture for #call<'a, C> {
+                    type Output = Result<#returns, C::Error>;
+                    type IntoFuture = #call_future<'a, #returns, C::Error>;
+
+                    fn into_future(self) -> Self::IntoFuture {
+                        Box::pin(self.caller.call::<#command>(self.params))
+                    }
+                }
+            },
+        ];
+
+        (method, items)
+    }
+}
+
+impl Domain {
+    ///
+    /// Generates the domain's client, along with the call of each command.
+    ///
+    pub(super) fn gen_facade(&self, span: Span, ctx: Option<util::Context>) -> Vec<syn::Item> {
+        let name = self.domain.original();
+        let client = m::NamedIdentifier::<conv::DomainClient>::new(name).rustify(span, None);
+        let caller = util::rust::Caller(span);
+
+        let (methods, calls): (Vec<_>, Vec<_>) = self
+            .commands
+            .iter()
+            .flatten()
+            .map(|c| c.gen_facade(span, ctx.clone()))
+            .unzip();
+
+        let docs = format!(
+            "Client of the `{name}` domain, calling its commands through any [Caller](crate::util::Caller)."
+        );
+        let docs = util::rust::rustdoc(&docs, span);
+
+        [
+            parse_quote_spanned! {span=>
+                #(#docs)*
+                pub struct #client<'a, C> {
+                    caller: &'a C,
+                }
+            },
+            parse_quote_spanned! {span=>
+                impl<C> Clone for #client<'_, C> {
+                    fn clone(&self) -> Self {
+                        *self
+                    }
+                }
+            },
+            parse_quote_spanned! {span=>
+                impl<C> Copy for #client<'_, C> {}
+            },
+            parse_quote_spanned! {span=>
+                #[allow(deprecated)]
+                impl<'a, C: #caller> #client<'a, C> {
+                    pub fn new(caller: &'a C) -> Self {
+                        Self { caller }
+                    }
+
+                    #(#methods)*
+                }
+            },
+        ]
+        .into_iter()
+        .chain(calls.into_iter().flatten())
+        .collect()
+    }
+}
+
+///
+/// Generates the `DomainClients` trait, with a method
+/// returning the client of each domain.
+///
+pub fn domain_clients(span: Span, protocols: &[Protocol]) -> Vec<syn::Item> {
+    let caller = util::rust::Caller(span);
+
+    let methods = protocols.iter().flat_map(|p| &p.domains).map(|d| {
+        let module = d.domain.clone().rustify(span, None);
+        let client =
+            m::NamedIdentifier::<conv::DomainClient>::new(d.domain.original()).rustify(span, None);
+
+        quote_spanned! {span=>
+            fn #module(&self) -> #module::#client<'_, Self> {
+                #module::#client::new(self)
+            }
+        }
+    });
+
+    let docs = util::rust::rustdoc(
+        "The client of every domain, for any [Caller](crate::util::Caller)\n\
+        (e.g. `connection.page().navigate(url).await`).",
+        span,
+    );
+
+    vec![
+        parse_quote_spanned! {span=>
+            #(#docs)*
+            pub trait DomainClients: #caller + Sized {
+                #(#methods)*
+            }
+        },
+        parse_quote_spanned! {span=>
+            impl<C: #caller> DomainClients for C {}
+        },
+    ]
+}
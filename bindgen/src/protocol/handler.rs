@@ -0,0 +1,190 @@
+//!
+//! Server-side handlers, for implementing a domain's commands:
+//! * `{Domain}Handler<Ctx>`: a method per command of the domain, taking
+//!   some server-defined context and the command's parameters (if any).
+//!   Every method fails with "method not found" unless implemented,
+//!   so servers only implement the commands they support.
+//! * An implementation of `Dispatch<H, Ctx>` by the domain's marker type,
+//!   calling the commands of any handler `H` by method name.
+//!
+
+use proc_macro2::Span;
+use quote::quote_spanned;
+use syn::parse_quote_spanned;
+
+use super::{
+    convention as conv,
+    modular::{self as m, Identifier},
+    rustify::deprecated_docs_experimental,
+    Command, Domain,
+};
+use crate::util::{self, Contextual, Rustify, ToPath};
+
+impl Command {
+    ///
+    /// Generates the command's method of its domain's handler.
+    ///
+    fn gen_handler(
+        &self,
+        span: Span,
+        ctx: Option<util::Context>,
+        domain: &str,
+    ) -> proc_macro2::TokenStream {
+        let ctx = ctx.next(self.name.clone());
+
+        let command = self.name.clone().rustify(span, ctx.clone());
+        let method =
+            m::NamedIdentifier::<conv::Method>::new(self.name.original()).rustify(span, None);
+        let id = format!("{domain}.{}", self.name.original());
+
+        let error = util::rust::ProtocolError(span);
+        let returns: syn::Path = match self.returns {
+            Some(_) => [format!("{command}Returns")]
+                .map(util::to_ident(span))
+                .to_path(),
+            None => util::rust::Nothing(span),
+        };
+
+        let (params, unused) = match self.parameters {
+            Some(_) => {
+                let params = syn::Ident::new(&format!("{command}Params"), span);
+                (
+                    Some(quote_spanned!(span=> params: #params)),
+                    quote_spanned!(span=> (ctx, params)),
+                )
+            }
+            None => (None, quote_spanned!(span=> ctx)),
+        };
+
+        let docs = deprecated_docs_experimental(
+            ctx,
+            span,
+            self.deprecated,
+            self.description.clone(),
+            self.experimental,
+        );
+
+        quote_spanned! {span=>
+            #(#docs)*
+            fn #method(
+                &mut self,
+                ctx: &mut Ctx,
+                #params
+            ) -> impl ::std::future::Future<Output = Result<#returns, #error>> + Send {
+                let _ = #unused;
+                ::std::future::ready(Err(#error::method_not_found(#id)))
+            }
+        }
+    }
+}
+
+impl Domain {
+    ///
+    /// Generates the domain's handler trait.
+    ///
+    pub(super) fn gen_handler(&self, span: Span, ctx: Option<util::Context>) -> syn::Item {
+        let name = self.domain.original();
+        let handler = m::NamedIdentifier::<conv::DomainHandler>::new(name).rustify(span, None);
+
+        let methods = self
+            .commands
+            .iter()
+            .flatten()
+            .map(|c| c.gen_handler(span, ctx.clone(), name));
+
+        let docs = format!(
+            "Handler of the `{name}` domain's commands, for servers implementing it,\n\
+            with some context `Ctx` of their own.\n\
+            \n\
+            Commands not implemented fail with `-32601` (\"method not found\")."
+        );
+        let docs = util::rust::rustdoc(&docs, span);
+
+        parse_quote_spanned! {span=>
+            #(#docs)*
+            #[allow(deprecated)]
+            pub trait #handler<Ctx: Send>: Send {
+                #(#methods)*
+            }
+        }
+    }
+}
+
+impl Command {
+    ///
+    /// Generates the command's arm of its domain's `Dispatch::dispatch`.
+    ///
+    fn gen_handle_arm(
+        &self,
+        span: Span,
+        ctx: Option<util::Context>,
+        domain: &str,
+    ) -> proc_macro2::TokenStream {
+        let ctx = ctx.next(self.name.clone());
+
+        let command = self.name.clone().rustify(span, ctx);
+        let method =
+            m::NamedIdentifier::<conv::Method>::new(self.name.original()).rustify(span, None);
+        let id = format!("{domain}.{}", self.name.original());
+
+        let (parse_params, to_result) =
+            (util::rust::parse_params(span), util::rust::to_result(span));
+
+        let call = match self.parameters {
+            Some(_) => quote_spanned! {span=>
+                let params = #parse_params::<#command>(params)?;
+                #to_result(handler.#method(ctx, params).await?)
+            },
+            None => quote_spanned! {span=>
+                let _ = params;
+                #to_result(handler.#method(ctx).await?)
+            },
+        };
+
+        quote_spanned! {span=>
+            #id => Some(Box::pin(async move { #call }))
+        }
+    }
+}
+
+impl Domain {
+    ///
+    /// Generates the implementation of the `Dispatch` trait by the domain's
+    /// marker type, for every implementation of the domain's handler trait.
+    ///
+    pub(super) fn gen_handler_impl(&self, span: Span, ctx: Option<util::Context>) -> syn::Item {
+        let name = self.domain.original();
+        let handler = m::NamedIdentifier::<conv::DomainHandler>::new(name).rustify(span, None);
+        let marker = m::NamedIdentifier::<conv::DomainMarker>::new(name).rustify(span, None);
+
+        let (dispatch, any, error, call_future) = (
+            util::rust::Dispatch(span),
+            util::rust::Any(span),
+            util::rust::ProtocolError(span),
+            util::rust::CallFuture(span),
+        );
+
+        let arms = self
+            .commands
+            .iter()
+            .flatten()
+            .map(|c| c.gen_handle_arm(span, ctx.clone(), name));
+
+        parse_quote_spanned! {span=>
+            #[allow(deprecated)]
+            impl<Ctx: Send, H: #handler<Ctx>> #dispatch<H, Ctx> for #marker {
+                fn dispatch<'a>(
+                    handler: &'a mut H,
+                    ctx: &'a mut Ctx,
+                    method: &str,
+                    params: #any,
+                ) -> Option<#call_future<'a, #any, #error>> {
+                    match method {
+                        #(#arms,)*
+                        _ => None,
+                    }
+                }
+            }
+        }
+    }
+}
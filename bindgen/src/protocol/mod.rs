@@ -1,11 +1,20 @@
 pub mod convention;
+pub mod dispatch;
+pub mod facade;
+pub mod handler;
 pub mod modular;
 pub mod parsing;
-mod post_ast;
+pub mod post_ast;
 pub mod rustify;
+pub mod schema;
+
+use std::{
+    collections::{HashMap, HashSet},
+    iter,
+};
 
 use convention as conv;
-use modular as m;
+use modular::{self as m, Identifier};
 
 ///
 /// The simplest of types.
@@ -33,6 +42,12 @@ pub enum Primitive {
     ///
     String,
 
+    ///
+    /// Binary data (`crate::util::Binary`): declared as a string,
+    /// base64-encoded in JSON, and a byte string in CBOR.
+    ///
+    Binary,
+
     ///
     /// Any type ([serde_json::Value])
     ///
@@ -319,3 +334,118 @@ pub struct Protocol {
     ///
     domains: Vec<Domain>,
 }
+
+impl Type {
+    ///
+    /// Other domains this type refers to (through `$ref`s).
+    ///
+    fn referenced_domains(&self) -> Vec<&str> {
+        use Type::*;
+
+        match self {
+            Reference {
+                path: m::TypePath(Some(domain), _),
+                ..
+            } => vec![domain.original().as_str()],
+            Array { item_type, .. } => item_type.referenced_domains(),
+            Object {
+                fields: Some(fields),
+                ..
+            } => fields
+                .iter()
+                .flat_map(|f| f.ty.referenced_domains())
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+impl Domain {
+    ///
+    /// Original (wire) name of this domain.
+    ///
+    pub fn name(&self) -> &str {
+        self.domain.original()
+    }
+
+    ///
+    /// Every domain this domain depends on, either declared
+    /// in its `dependencies`, or referenced by its types.
+    ///
+    fn references(&self) -> Vec<&str> {
+        let declared = self
+            .dependencies
+            .iter()
+            .flatten()
+            .map(|DomainDependency(d)| d.original().as_str());
+
+        let types = self.types.iter().flatten().map(|t| &t.ty);
+
+        let fields = iter::empty()
+            .chain(self.commands.iter().flatten().flat_map(|c| {
+                c.parameters
+                    .iter()
+                    .chain(c.returns.iter())
+                    .flatten()
+            }))
+            .chain(
+                self.events
+                    .iter()
+                    .flatten()
+                    .flat_map(|e| e.parameters.iter().flatten()),
+            )
+            .map(|f| &f.ty);
+
+        declared
+            .chain(types.chain(fields).flat_map(Type::referenced_domains))
+            .collect()
+    }
+}
+
+///
+/// Only keep the `requested` domains in the protocols,
+/// along with every domain they (transitively) depend on.
+///
+/// Fails on the first requested domain that does not exist,
+/// or domain they depend on which is not in any of the protocols.
+///
+pub fn retain_domains(
+    protocols: &mut [Protocol],
+    requested: &[String],
+) -> Result<(), crate::Error> {
+    let all = protocols
+        .iter()
+        .flat_map(|p| p.domains.iter())
+        .map(|d| (d.name(), d))
+        .collect::<HashMap<_, _>>();
+
+    if let Some(unknown) = requested.iter().find(|d| !all.contains_key(d.as_str())) {
+        return Err(crate::Error::UnknownDomain(unknown.clone()));
+    }
+
+    let mut keep = HashSet::new();
+    let mut stack = requested.iter().map(String::as_str).collect::<Vec<_>>();
+
+    while let Some(name) = stack.pop() {
+        let Some(domain) = all.get(name).filter(|_| keep.insert(name.to_string())) else {
+            continue;
+        };
+
+        for dependency in domain.references() {
+            if !all.contains_key(dependency) {
+                return Err(crate::Error::MissingDependency {
+                    domain: name.to_string(),
+                    dependency: dependency.to_string(),
+                });
+            }
+
+            stack.push(dependency);
+        }
+    }
+
+    for protocol in protocols.iter_mut() {
+        protocol.domains.retain(|d| keep.contains(d.name()));
+    }
+
+    Ok(())
+}
@@ -127,6 +127,8 @@ impl<NC: NamingConvention> ToString for NamedIdentifier<NC> {
 ///
 /// Type path in the format `[Domain].<Type>`
 ///
+/// Rustified as `super::domain::Type` for cross-domain references.
+///
 #[derive(Debug, Clone)]
 pub struct TypePath(
     pub(crate) Option<NamedIdentifier<conv::Domain>>,
@@ -145,13 +147,10 @@ impl Rustify for TypePath {
             .map(PathSegment::from)
             .collect::<Vec<_>>();
 
+        // Domains are sibling modules, so cross-domain references
+        // stay valid wherever the bindings are generated.
         if segments.len() == 2 {
-            let root = ["crate", "protocol"]
-                .map(util::to_ident(span))
-                .map(PathSegment::from)
-                .into_iter();
-
-            segments = root.chain(segments).collect();
+            segments.insert(0, PathSegment::from(util::rust::super_(span)));
         }
 
         syn::TypePath {
@@ -201,6 +201,31 @@ impl<'de> Deserialize<'de> for super::Type {
     }
 }
 
+///
+/// Note ending the description of binary values,
+/// which are otherwise declared as strings.
+///
+const BINARY_NOTE: &str = "(Encoded as a base64 string when passed over JSON)";
+
+///
+/// Turns a string type into [Binary](protocol::Primitive::Binary)
+/// if its description says it is one.
+///
+fn binary(ty: super::Type, description: Option<&m::Documentation>) -> super::Type {
+    let is_binary = description.is_some_and(|doc| doc.0.iter().any(|ln| ln.contains(BINARY_NOTE)));
+
+    match ty {
+        super::Type::Primitive {
+            ty: protocol::Primitive::String,
+            optional,
+        } if is_binary => super::Type::Primitive {
+            ty: protocol::Primitive::Binary,
+            optional,
+        },
+        ty => ty,
+    }
+}
+
 impl<'de> Deserialize<'de> for super::Field {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -208,12 +233,19 @@ impl<'de> Deserialize<'de> for super::Field {
     {
         let mut raw: Map<String, serde_json::Value> = Deserialize::deserialize(deserializer)?;
 
+        let name = raw.take::<_, D>("name", "Missing field `name` from Field declaration")?;
+        let description = raw.take_optional::<m::Documentation, D>("description")?;
+        let experimental = raw.take_optional::<_, D>("experimental")?;
+        let deprecated = raw.take_optional::<_, D>("deprecated")?;
+        let ty =
+            serde_json::from_value(serde_json::Value::Object(raw)).map_err(D::Error::custom)?;
+
         Ok(Self {
-            name: raw.take::<_, D>("name", "Missing field `name` from Field declaration")?,
-            description: raw.take_optional::<_, D>("description")?,
-            experimental: raw.take_optional::<_, D>("experimental")?,
-            deprecated: raw.take_optional::<_, D>("deprecated")?,
-            ty: serde_json::from_value(serde_json::Value::Object(raw)).map_err(D::Error::custom)?,
+            name,
+            ty: binary(ty, description.as_ref()),
+            description,
+            experimental,
+            deprecated,
         })
     }
 }
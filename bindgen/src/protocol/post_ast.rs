@@ -4,12 +4,13 @@
 //! Right now:
 //! * Box-ing of recursive types.
 //! * Default for enum.
+//! * Re-rooting of `crate::...` paths.
 //!
 
 use std::iter;
 
 use proc_macro2::Span;
-use syn::punctuated::Punctuated;
+use syn::{punctuated::Punctuated, visit_mut::VisitMut};
 
 use crate::util::{self, ToTypedPath};
 
@@ -132,6 +133,40 @@ pub fn defaultify<'a>(span: Span, iter: impl Iterator<Item = &'a mut syn::ItemEn
     });
 }
 
+///
+/// Rewrites paths starting with `crate` to start with another root.
+///
+struct Reroot<'a>(&'a syn::Path);
+
+impl VisitMut for Reroot<'_> {
+    fn visit_path_mut(&mut self, path: &mut syn::Path) {
+        let is_crate = path.leading_colon.is_none()
+            && path.segments.first().is_some_and(|s| s.ident == "crate");
+
+        if is_crate {
+            let rest = path.segments.iter().skip(1).cloned().collect::<Vec<_>>();
+            path.leading_colon = self.0.leading_colon;
+            path.segments = self.0.segments.iter().cloned().chain(rest).collect();
+        }
+
+        syn::visit_mut::visit_path_mut(self, path);
+    }
+}
+
+///
+/// Points every `crate::...` path (e.g. `crate::util::Command`)
+/// at `root` instead, for bindings generated outside of this crate.
+///
+pub fn reroot(file: &mut syn::File, root: &syn::Path) {
+    let unchanged = root.leading_colon.is_none()
+        && root.segments.len() == 1
+        && root.segments.first().is_some_and(|s| s.ident == "crate");
+
+    if !unchanged {
+        Reroot(root).visit_file_mut(file);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::iter;
@@ -11,7 +11,7 @@ use super::{
 };
 use convert_case::{Case, Casing};
 use proc_macro2::Span;
-use syn::punctuated::Punctuated;
+use syn::{parse_quote_spanned, punctuated::Punctuated};
 
 use crate::protocol::modular::Identifier;
 use crate::util::ToPath;
@@ -32,7 +32,8 @@ impl Rustify for Primitive {
             Number => vec!["f64"],
             Integer => vec!["i64"],
             String => vec!["String"],
-            Any => vec!["serde_json", "Value"],
+            Binary => vec!["crate", "util", "Binary"],
+            Any => vec!["crate", "util", "Any"],
         }
         .into_iter()
         .map(util::to_ident(span))
@@ -251,7 +252,7 @@ impl Rustify for Type {
     }
 }
 
-fn deprecated_docs_experimental(
+pub(super) fn deprecated_docs_experimental(
     ctx: Option<util::Context>,
     span: Span,
     deprecated: Option<m::Deperecated>,
@@ -445,9 +446,6 @@ impl Command {
             }
         };
 
-        println!("Parameters: {:?}", self.parameters);
-        println!("Returns: {:?}", self.returns);
-
         let params = assoc_type(
             "Parameters",
             if_def(
@@ -462,13 +460,15 @@ impl Command {
             if_def(self.returns.is_some(), "Returns", util::rust::Nothing(span)),
         );
 
-        let error = assoc_type("Error", util::rust::Infallible(span));
+        let error = assoc_type("Error", util::rust::ProtocolError(span));
 
         let (d, s) = match ctx {
             Some(util::Context::Item(d, s)) => (d, s),
             _ => unimplemented!(),
         };
 
+        let domain = assoc_type("Domain", [domain_marker(span, &d)].to_path());
+
         let stmt = syn::Stmt::Expr(
             syn::Expr::Lit(syn::ExprLit {
                 attrs: Default::default(),
@@ -515,7 +515,7 @@ impl Command {
             trait_: Some((None, trait_path, Default::default())),
             self_ty: Box::new([ident.clone()].to_type_path().into()),
             brace_token: Default::default(),
-            items: [params, returns, error]
+            items: [params, returns, error, domain]
                 .map(syn::ImplItem::Type)
                 .into_iter()
                 .chain(iter::once(id_fn))
@@ -702,6 +702,9 @@ impl Rustify for Event {
             },
         });
 
+        let marker = domain_marker(span, &d);
+        let domain: syn::ImplItem = parse_quote_spanned!(span=> type Domain = #marker;);
+
         let trait_path = util::rust::Event(span);
 
         let impl_block = syn::Item::Impl(syn::ItemImpl {
@@ -713,7 +716,7 @@ impl Rustify for Event {
             trait_: Some((None, trait_path, Default::default())),
             self_ty: Box::new([ident.clone()].to_type_path().into()),
             brace_token: Default::default(),
-            items: vec![id_fn, id_fn2],
+            items: vec![domain, id_fn, id_fn2],
         });
 
         additional.chain([strct, impl_block]).collect()
@@ -741,7 +744,89 @@ impl Rustify for DomainDependency {
     }
 }
 
+///
+/// Identifier of the marker type of domain `d` (e.g. `PageDomain`).
+///
+fn domain_marker(span: Span, d: &impl Identifier) -> syn::Ident {
+    m::NamedIdentifier::<conv::DomainMarker>::new(d.original()).rustify(span, None)
+}
+
 impl Domain {
+    ///
+    /// Generates the domain's marker type, along with
+    /// its implementation of the `Domain` trait.
+    ///
+    fn gen_marker(&self, span: Span) -> [syn::Item; 2] {
+        let name = self.domain.original();
+        let ident = domain_marker(span, &self.domain);
+        let trait_path = util::rust::Domain(span);
+
+        let command = |method: &str| {
+            self.commands
+                .iter()
+                .flatten()
+                .find(|c| c.name.original() == method)
+                .map(|c| (format!("{name}.{method}"), c.name.clone().rustify(span, None)))
+        };
+
+        let (enable, disable) = (command("enable"), command("disable"));
+
+        let links = [("Enable", &enable), ("Disable", &disable)]
+            .into_iter()
+            .filter_map(|(kind, c)| c.as_ref().map(|(_, c)| format!("* {kind}: [{c}]")));
+
+        let docs = iter::once(format!("Marker type of the `{name}` domain."))
+            .chain(
+                (enable.is_some() || disable.is_some()).then_some(String::new()),
+            )
+            .chain(links)
+            .collect::<Vec<_>>()
+            .join("\n");
+        let docs = util::rust::rustdoc(&docs, span);
+
+        let dependencies = self
+            .dependencies
+            .iter()
+            .flatten()
+            .map(|d| d.0.original());
+        let experimental = self.experimental.is_some();
+        let [enable, disable] = [enable, disable].map(|c| match c {
+            Some((method, _)) => quote::quote_spanned!(span=> Some(#method)),
+            None => quote::quote_spanned!(span=> None),
+        });
+
+        [
+            parse_quote_spanned! {span=>
+                #(#docs)*
+                #[derive(Debug, Clone, Copy, Default)]
+                pub struct #ident;
+            },
+            parse_quote_spanned! {span=>
+                impl #trait_path for #ident {
+                    fn name() -> &'static str {
+                        #name
+                    }
+
+                    fn dependencies() -> &'static [&'static str] {
+                        &[#(#dependencies),*]
+                    }
+
+                    fn experimental() -> bool {
+                        #experimental
+                    }
+
+                    fn enable() -> Option<&'static str> {
+                        #enable
+                    }
+
+                    fn disable() -> Option<&'static str> {
+                        #disable
+                    }
+                }
+            },
+        ]
+    }
+
     fn add_derive_attr(span: Span) -> impl Fn(syn::Item) -> syn::Item {
         move |mut item| {
             match &mut item {
@@ -764,7 +849,13 @@ impl Rustify for Domain {
 
     fn rustify(self, span: Span, ctx: Option<util::Context>) -> Self::Output {
         let ctx = ctx.next(self.domain.clone());
-        let ident = self.domain.rustify(span, ctx.clone());
+        let ident = self.domain.clone().rustify(span, ctx.clone());
+        let marker = self.gen_marker(span);
+        let facade = self.gen_facade(span, ctx.clone());
+        let handler = [
+            self.gen_handler(span, ctx.clone()),
+            self.gen_handler_impl(span, ctx.clone()),
+        ];
 
         let attrs = deprecated_docs_experimental(
             ctx.clone(),
@@ -806,6 +897,9 @@ impl Rustify for Domain {
             .chain(commands)
             .chain(events)
             .map(Self::add_derive_attr(span))
+            .chain(marker)
+            .chain(facade)
+            .chain(handler)
             .collect();
 
         syn::ItemMod {
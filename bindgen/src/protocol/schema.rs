@@ -0,0 +1,387 @@
+//!
+//! Translation of the protocol definition into a
+//! [JSON Schema (draft 2020-12)](https://json-schema.org/draft/2020-12/schema) document.
+//!
+//! Every type declaration becomes a definition named `Domain.Type`,
+//! and every command and event gets its own definitions:
+//! * `Domain.command.params` and `Domain.command.returns`
+//! * `Domain.event.event`
+//!
+
+use serde_json::{json, Map, Value};
+
+use super::{
+    modular::{self as m, Identifier},
+    Command, Domain, Event, Field, Primitive, Protocol, Type, TypeDeclaration,
+};
+use crate::util::{Context, Contextual};
+
+///
+/// Dialect of the generated JSON Schema.
+///
+pub const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+///
+/// Trait for converting the protocol definition into
+/// JSON Schema definitions.
+///
+pub trait Schemify {
+    ///
+    /// JSON Schema equivalent of the definition.
+    ///
+    type Output;
+
+    ///
+    /// Convert `self` into JSON Schema.
+    ///
+    fn schemify(&self, ctx: Option<Context>) -> Self::Output;
+}
+
+///
+/// Name of a definition in `$defs`.
+///
+fn definition(domain: &str, name: &str, suffix: Option<&str>) -> String {
+    match suffix {
+        Some(suffix) => format!("{domain}.{name}.{suffix}"),
+        None => format!("{domain}.{name}"),
+    }
+}
+
+///
+/// Original (wire) name of the domain we are currently in.
+///
+fn current_domain(ctx: &Option<Context>) -> String {
+    Contextual::iter(ctx)
+        .next()
+        .map(|d| d.original().clone())
+        .expect("Insufficient context for JSON Schema generation")
+}
+
+///
+/// Adds the `description` and `deprecated` annotations to a schema.
+///
+fn annotate(
+    mut schema: Value,
+    description: &Option<m::Documentation>,
+    deprecated: &Option<m::Deperecated>,
+) -> Value {
+    if let Value::Object(ref mut obj) = schema {
+        if let Some(m::Documentation(lines)) = description {
+            obj.insert("description".into(), lines.join("\n").into());
+        }
+
+        if deprecated.is_some() {
+            obj.insert("deprecated".into(), true.into());
+        }
+    }
+
+    schema
+}
+
+///
+/// Schema for an object with the provided (optional) fields.
+///
+fn object(fields: Option<&Vec<Field>>, ctx: &Option<Context>) -> Value {
+    let Some(fields) = fields else {
+        return json!({ "type": "object" });
+    };
+
+    let required = fields
+        .iter()
+        .filter(|f| !f.ty.is_optional())
+        .map(|f| Value::from(f.name.original().as_str()))
+        .collect::<Vec<_>>();
+
+    let properties = fields
+        .iter()
+        .map(|f| f.schemify(ctx.clone()))
+        .collect::<Map<_, _>>();
+
+    let mut schema = json!({ "type": "object", "properties": properties });
+
+    if !required.is_empty() {
+        schema["required"] = Value::Array(required);
+    }
+
+    schema
+}
+
+impl Schemify for Primitive {
+    type Output = Value;
+
+    fn schemify(&self, _: Option<Context>) -> Self::Output {
+        use Primitive::*;
+
+        match self {
+            Boolean => json!({ "type": "boolean" }),
+            Number => json!({ "type": "number" }),
+            Integer => json!({ "type": "integer" }),
+            String => json!({ "type": "string" }),
+            Binary => json!({ "type": "string", "contentEncoding": "base64" }),
+            Any => json!({}),
+        }
+    }
+}
+
+impl Schemify for m::TypePath {
+    type Output = Value;
+
+    fn schemify(&self, ctx: Option<Context>) -> Self::Output {
+        let domain = self
+            .0
+            .as_ref()
+            .map(|d| d.original().clone())
+            .unwrap_or_else(|| current_domain(&ctx));
+
+        json!({ "$ref": format!("#/$defs/{}", definition(&domain, self.1.original(), None)) })
+    }
+}
+
+impl Schemify for Type {
+    type Output = Value;
+
+    fn schemify(&self, ctx: Option<Context>) -> Self::Output {
+        use Type::*;
+
+        match self {
+            Primitive { ty, .. } => ty.schemify(ctx),
+            Reference { path, .. } => path.schemify(ctx),
+            Array { item_type, .. } => json!({
+                "type": "array",
+                "items": item_type.schemify(ctx),
+            }),
+            Object { fields, .. } => object(fields.as_ref(), &ctx),
+            Enum { values, .. } => json!({
+                "type": "string",
+                "enum": values.iter().map(|v| v.original().as_str()).collect::<Vec<_>>(),
+            }),
+        }
+    }
+}
+
+impl Schemify for Field {
+    type Output = (String, Value);
+
+    fn schemify(&self, ctx: Option<Context>) -> Self::Output {
+        (
+            self.name.original().clone(),
+            annotate(self.ty.schemify(ctx), &self.description, &self.deprecated),
+        )
+    }
+}
+
+impl Schemify for TypeDeclaration {
+    type Output = (String, Value);
+
+    fn schemify(&self, ctx: Option<Context>) -> Self::Output {
+        (
+            definition(&current_domain(&ctx), self.id.original(), None),
+            annotate(self.ty.schemify(ctx), &self.description, &self.deprecated),
+        )
+    }
+}
+
+impl Schemify for Command {
+    type Output = [(String, Value); 2];
+
+    fn schemify(&self, ctx: Option<Context>) -> Self::Output {
+        let domain = current_domain(&ctx);
+        let name = self.name.original();
+
+        let params = annotate(
+            object(self.parameters.as_ref(), &ctx),
+            &self.description,
+            &self.deprecated,
+        );
+
+        let returns = annotate(
+            object(self.returns.as_ref(), &ctx),
+            &Some(m::Documentation(vec![format!(
+                "Return value for `{domain}.{name}`."
+            )])),
+            &self.deprecated,
+        );
+
+        [
+            (definition(&domain, name, Some("params")), params),
+            (definition(&domain, name, Some("returns")), returns),
+        ]
+    }
+}
+
+impl Schemify for Event {
+    type Output = (String, Value);
+
+    fn schemify(&self, ctx: Option<Context>) -> Self::Output {
+        let domain = current_domain(&ctx);
+
+        (
+            definition(&domain, self.name.original(), Some("event")),
+            annotate(
+                object(self.parameters.as_ref(), &ctx),
+                &self.description,
+                &self.deprecated,
+            ),
+        )
+    }
+}
+
+impl Schemify for Domain {
+    type Output = Vec<(String, Value)>;
+
+    fn schemify(&self, ctx: Option<Context>) -> Self::Output {
+        let ctx = ctx.next(self.domain.clone());
+
+        let types = self
+            .types
+            .iter()
+            .flatten()
+            .map(|t| t.schemify(ctx.clone()));
+
+        let commands = self
+            .commands
+            .iter()
+            .flatten()
+            .flat_map(|c| c.schemify(ctx.clone()));
+
+        let events = self
+            .events
+            .iter()
+            .flatten()
+            .map(|e| e.schemify(ctx.clone()));
+
+        types.chain(commands).chain(events).collect()
+    }
+}
+
+impl Schemify for Protocol {
+    type Output = Value;
+
+    fn schemify(&self, _: Option<Context>) -> Self::Output {
+        let ctx = Some(Context::Protocol);
+
+        let defs = self
+            .domains
+            .iter()
+            .flat_map(|d| d.schemify(ctx.clone()))
+            .collect::<Map<_, _>>();
+
+        json!({
+            "$schema": DIALECT,
+            "title": "Chrome DevTools Protocol",
+            "description": format!("Chrome DevTools Protocol, version {}.", self.version.to_string()),
+            "$defs": defs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::Schemify;
+    use crate::protocol::Protocol;
+
+    ///
+    /// Collect every `$ref` in a schema.
+    ///
+    fn refs<'a>(schema: &'a Value, out: &mut Vec<&'a str>) {
+        match schema {
+            Value::Object(obj) => obj.iter().for_each(|(k, v)| match (k.as_str(), v) {
+                ("$ref", Value::String(r)) => out.push(r),
+                _ => refs(v, out),
+            }),
+            Value::Array(arr) => arr.iter().for_each(|v| refs(v, out)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_command_schema() {
+        let protocol: Protocol = serde_json::from_str(
+            r#"{
+            "version": { "major": "1", "minor": "3" },
+            "domains": [{
+                "domain": "Page",
+                "types": [{ "id": "FrameId", "type": "string" }],
+                "commands": [{
+                    "name": "navigate",
+                    "description": "Navigates current page to the given URL.",
+                    "parameters": [
+                        { "name": "url", "type": "string" },
+                        { "name": "frameId", "optional": true, "$ref": "FrameId" },
+                        { "name": "loaderId", "optional": true, "$ref": "Network.LoaderId" }
+                    ]
+                }],
+                "events": [{ "name": "loadEventFired" }]
+            }]
+        }"#,
+        )
+        .expect("valid parse");
+
+        let schema = protocol.schemify(None);
+        let defs = &schema["$defs"];
+
+        assert_eq!(defs["Page.FrameId"], json!({ "type": "string" }));
+        assert_eq!(
+            defs["Page.navigate.params"],
+            json!({
+                "type": "object",
+                "description": "Navigates current page to the given URL.",
+                "properties": {
+                    "url": { "type": "string" },
+                    "frameId": { "$ref": "#/$defs/Page.FrameId" },
+                    "loaderId": { "$ref": "#/$defs/Network.LoaderId" },
+                },
+                "required": ["url"],
+            })
+        );
+        assert_eq!(defs["Page.navigate.returns"]["type"], "object");
+        assert_eq!(defs["Page.loadEventFired.event"], json!({ "type": "object" }));
+    }
+
+    #[test]
+    fn test_binary_schema() {
+        let protocol: Protocol = serde_json::from_str(
+            r#"{
+            "version": { "major": "1", "minor": "3" },
+            "domains": [{
+                "domain": "Page",
+                "commands": [{
+                    "name": "captureScreenshot",
+                    "returns": [
+                        {
+                            "name": "data",
+                            "description": "Base64-encoded image data. (Encoded as a base64 string when passed over JSON)",
+                            "type": "string"
+                        },
+                        { "name": "format", "description": "Image format.", "type": "string" }
+                    ]
+                }]
+            }]
+        }"#,
+        )
+        .expect("valid parse");
+
+        let schema = protocol.schemify(None);
+        let properties = &schema["$defs"]["Page.captureScreenshot.returns"]["properties"];
+
+        assert_eq!(properties["data"]["type"], "string");
+        assert_eq!(properties["data"]["contentEncoding"], "base64");
+        assert_eq!(properties["format"].get("contentEncoding"), None);
+    }
+
+    #[test]
+    fn test_refs_resolve() {
+        let schema = crate::protocols_to_json_schema(crate::fetch_protocols());
+
+        let mut all = vec![];
+        refs(&schema, &mut all);
+
+        assert!(!all.is_empty());
+        for r in all {
+            let name = r.strip_prefix("#/$defs/").expect("local reference");
+            assert!(schema["$defs"].get(name).is_some(), "Dangling reference `{r}`");
+        }
+    }
+}
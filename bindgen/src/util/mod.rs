@@ -320,6 +320,99 @@ pub mod rust {
         ["crate", "util", "Event"].map(to_ident(span)).to_path()
     }
 
+    ///
+    /// Path for the `Domain` trait, implemented
+    /// by each domain's marker type.
+    ///
+    #[allow(non_snake_case)]
+    pub fn Domain(span: Span) -> syn::Path {
+        ["crate", "util", "Domain"].map(to_ident(span)).to_path()
+    }
+
+    ///
+    /// Path for the `Caller` trait, which the
+    /// domain client facades call commands through.
+    ///
+    #[allow(non_snake_case)]
+    pub fn Caller(span: Span) -> syn::Path {
+        ["crate", "util", "Caller"].map(to_ident(span)).to_path()
+    }
+
+    ///
+    /// Path for the `CallFuture` type alias,
+    /// the future of a command called through a facade.
+    ///
+    #[allow(non_snake_case)]
+    pub fn CallFuture(span: Span) -> syn::Path {
+        ["crate", "util", "CallFuture"]
+            .map(to_ident(span))
+            .to_path()
+    }
+
+    ///
+    /// Path for the `Method` trait, implemented by
+    /// enums tagged with a method name.
+    ///
+    #[allow(non_snake_case)]
+    pub fn Method(span: Span) -> syn::Path {
+        ["crate", "util", "Method"].map(to_ident(span)).to_path()
+    }
+
+    ///
+    /// Path to the helper serializing a `{ "method", "params" }` pair.
+    ///
+    pub fn serialize_method(span: Span) -> syn::Path {
+        ["crate", "util", "serialize_method"]
+            .map(to_ident(span))
+            .to_path()
+    }
+
+    ///
+    /// Path to the helper deserializing a `Method` from
+    /// a `{ "method", "params" }` pair.
+    ///
+    pub fn deserialize_method(span: Span) -> syn::Path {
+        ["crate", "util", "deserialize_method"]
+            .map(to_ident(span))
+            .to_path()
+    }
+
+    ///
+    /// Path to the `Any` type alias
+    /// (value of the protocol's `any` type, or raw params).
+    ///
+    #[allow(non_snake_case)]
+    pub fn Any(span: Span) -> syn::Path {
+        ["crate", "util", "Any"].map(to_ident(span)).to_path()
+    }
+
+    ///
+    /// Path for the `Dispatch` trait, through which
+    /// servers route calls to the domain handlers.
+    ///
+    #[allow(non_snake_case)]
+    pub fn Dispatch(span: Span) -> syn::Path {
+        ["crate", "util", "Dispatch"].map(to_ident(span)).to_path()
+    }
+
+    ///
+    /// Path to the `parse_params` function,
+    /// deserializing a command's raw params.
+    ///
+    pub fn parse_params(span: Span) -> syn::Path {
+        ["crate", "util", "parse_params"]
+            .map(to_ident(span))
+            .to_path()
+    }
+
+    ///
+    /// Path to the `to_result` function,
+    /// serializing a command's returns.
+    ///
+    pub fn to_result(span: Span) -> syn::Path {
+        ["crate", "util", "to_result"].map(to_ident(span)).to_path()
+    }
+
     ///
     /// Path to the `Nothing` struct
     /// (no parameters/return type).
@@ -330,12 +423,12 @@ pub mod rust {
     }
 
     ///
-    /// Path to the `Infalible` enum
-    /// (no possible errors).
+    /// Path to the `ProtocolError` struct
+    /// (error returned by a command).
     ///
     #[allow(non_snake_case)]
-    pub fn Infallible(span: Span) -> syn::Path {
-        ["crate", "util", "Infallible"]
+    pub fn ProtocolError(span: Span) -> syn::Path {
+        ["crate", "util", "ProtocolError"]
             .map(to_ident(span))
             .to_path()
     }
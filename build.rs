@@ -1,13 +1,27 @@
 use std::{env, path::Path, fs::File, io::Write};
 
-use chrome_devtools_bindgen::generate_protocol_bindings;
+use chrome_devtools_bindgen::{
+    fetch_protocols, generate_json_schema, generate_protocol_bindings, generate_protocol_json,
+};
 
 fn main() {
     let out_dir = env::var("OUT_DIR").unwrap();
+    let sources = fetch_protocols();
+
     let protocol_path = Path::new(&out_dir).join("__protocol.rs");
     let mut f = File::create(protocol_path).unwrap();
 
-    let source_code = generate_protocol_bindings();
+    let source_code = generate_protocol_bindings(&sources);
 
     f.write_all(source_code.as_bytes()).expect("Not writeable!");
+
+    let schema_path = Path::new(&out_dir).join("__protocol.schema.json");
+    let mut f = File::create(schema_path).unwrap();
+
+    f.write_all(generate_json_schema(&sources).as_bytes()).expect("Not writeable!");
+
+    let definition_path = Path::new(&out_dir).join("__protocol.json");
+    let mut f = File::create(definition_path).unwrap();
+
+    f.write_all(generate_protocol_json(&sources).as_bytes()).expect("Not writeable!");
 }
\ No newline at end of file
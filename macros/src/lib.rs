@@ -0,0 +1,196 @@
+//!
+//! # Protocol macro
+//!
+//! Proc-macro front end for the bindings generator,
+//! used to generate protocol bindings inline in another crate.
+//!
+//! Re-exported as `chrome_devtools_api::protocol!`.
+//!
+
+use std::{env, fs, path::PathBuf};
+
+use chrome_devtools_bindgen::{fetch_protocols, generate, Error, Options};
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{
+    bracketed,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Ident, LitStr, Token,
+};
+
+///
+/// Arguments to the [protocol!] macro.
+///
+#[derive(Default)]
+struct Args {
+    ///
+    /// Protocol definition files, relative to the invoking crate's manifest.
+    ///
+    paths: Vec<LitStr>,
+
+    ///
+    /// Domains to generate (all if not specified).
+    ///
+    domains: Option<Vec<Ident>>,
+
+    ///
+    /// Path to the `chrome_devtools_api` crate.
+    ///
+    root: Option<syn::Path>,
+}
+
+///
+/// Parses either a single item, or a bracketed list of items:
+/// `a` or `[a, b, c]`.
+///
+fn one_or_many<T: Parse>(input: ParseStream) -> syn::Result<Vec<T>> {
+    if !input.peek(syn::token::Bracket) {
+        return Ok(vec![input.parse()?]);
+    }
+
+    let content;
+    bracketed!(content in input);
+
+    Ok(Punctuated::<T, Token![,]>::parse_terminated(&content)?
+        .into_iter()
+        .collect())
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = Args::default();
+
+        while !input.is_empty() {
+            if input.peek(Token![crate]) {
+                input.parse::<Token![crate]>()?;
+                input.parse::<Token![=]>()?;
+                args.root = Some(input.parse()?);
+            } else {
+                let key: Ident = input.parse()?;
+                input.parse::<Token![=]>()?;
+
+                match key.to_string().as_str() {
+                    "path" => args.paths = one_or_many(input)?,
+                    "domains" => args.domains = Some(one_or_many(input)?),
+                    _ => {
+                        return Err(syn::Error::new(
+                            key.span(),
+                            "Unknown argument: expected `path`, `domains`, or `crate`",
+                        ))
+                    }
+                }
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+fn expand(args: Args) -> syn::Result<TokenStream> {
+    let span = Span::call_site();
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap_or_default());
+
+    let files = args
+        .paths
+        .iter()
+        .map(|lit| {
+            let path = manifest_dir.join(lit.value());
+            fs::read_to_string(&path)
+                .map(|src| (path.display().to_string(), src))
+                .map_err(|e| {
+                    syn::Error::new(
+                        lit.span(),
+                        format!("Could not read `{}`: {e}", path.display()),
+                    )
+                })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    // Recompile the invoking crate whenever a protocol file changes.
+    let tracked = files
+        .iter()
+        .map(|(path, _)| quote! { const _: &str = include_str!(#path); });
+
+    let sources = match files.is_empty() {
+        true => fetch_protocols().to_vec(),
+        false => files.iter().map(|(_, src)| src.clone()).collect(),
+    };
+
+    let options = Options {
+        sources,
+        domains: args
+            .domains
+            .as_ref()
+            .map(|d| d.iter().map(ToString::to_string).collect()),
+        root: args
+            .root
+            .unwrap_or_else(|| syn::parse_quote!(::chrome_devtools_api)),
+        span,
+    };
+
+    let file = generate(options).map_err(|e| match e {
+        Error::UnknownDomain(ref name) => args
+            .domains
+            .iter()
+            .flatten()
+            .find(|d| d.to_string().eq(name))
+            .map(|d| syn::Error::new(d.span(), &e))
+            .unwrap_or_else(|| syn::Error::new(span, &e)),
+        Error::Parse(_) | Error::MissingDependency { .. } => args
+            .paths
+            .first()
+            .map(|lit| syn::Error::new(lit.span(), &e))
+            .unwrap_or_else(|| syn::Error::new(span, &e)),
+    })?;
+
+    let items = file.items;
+
+    Ok(quote! {
+        #(#tracked)*
+
+        #(
+            #[allow(deprecated)]
+            #[allow(clippy::enum_variant_names)]
+            #[allow(clippy::large_enum_variant)]
+            #items
+        )*
+    })
+}
+
+///
+/// Generates protocol bindings inline, as one module per domain,
+/// along with the protocol-wide `MethodCall` and `AnyEvent` enums.
+///
+/// ```ignore
+/// chrome_devtools_api::protocol!(
+///     path = "my_protocol.json",
+///     domains = [Page, Runtime],
+/// );
+/// ```
+///
+/// ## Arguments
+/// * `path = "..."` or `path = ["...", ...]`:
+///   protocol definition files, relative to the crate's `Cargo.toml`
+///   (defaults to the protocols bundled with `chrome_devtools_api`).
+/// * `domains = [...]`: only generate these domains, along with
+///   every domain they depend on (defaults to all domains), all of which
+///   must be in the protocol files.
+/// * `crate = ...`: path to the `chrome_devtools_api` crate
+///   (defaults to `::chrome_devtools_api`).
+///
+/// The generated items derive `serde`'s traits, so the invoking crate
+/// needs to depend on `serde` and `serde_json`.
+///
+#[proc_macro]
+pub fn protocol(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let args = syn::parse_macro_input!(input as Args);
+
+    expand(args)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,246 @@
+//!
+//! Synchronous client, over `std::net` and `tungstenite`:
+//! ```no_run
+//! use chrome_devtools_api::{client::blocking::Connection, protocol::page};
+//!
+//! let mut connection = Connection::connect("ws://127.0.0.1:9222/devtools/page/...")?;
+//! connection.call::<page::Enable>(Default::default())?;
+//!
+//! for event in connection.events::<page::LoadEventFiredEvent>() {
+//!     println!("Loaded at {}", event?.params.timestamp);
+//! }
+//! # Ok::<(), chrome_devtools_api::client::Error>(())
+//! ```
+//!
+
+use std::{
+    collections::VecDeque,
+    io::{Read, Write},
+    net::TcpStream,
+};
+
+use serde_json::Value;
+use tungstenite::{stream::MaybeTlsStream, Message as WsMessage, WebSocket};
+
+use super::{
+    dispatch::{Dispatcher, Incoming},
+    Error,
+};
+use crate::util::{CallId, Command, Event, Notification, RawNotification, SessionId};
+
+///
+/// A blocking connection.
+///
+/// Calls are made one at a time, and block until answered.
+/// Events received in the meantime are kept until read
+/// with [Connection::next_event] or [Connection::events].
+///
+pub struct Connection<S = MaybeTlsStream<TcpStream>> {
+    socket: WebSocket<S>,
+    dispatcher: Dispatcher<()>,
+    next_id: CallId,
+    events: VecDeque<RawNotification>,
+}
+
+impl Connection {
+    ///
+    /// Connects to a WebSocket debugger URL,
+    /// such as `ws://127.0.0.1:9222/devtools/browser/<id>`.
+    ///
+    pub fn connect(url: &str) -> Result<Self, Error> {
+        let (socket, _) = tungstenite::connect(url)?;
+        Ok(Self::new(socket))
+    }
+}
+
+impl<S: Read + Write> Connection<S> {
+    ///
+    /// Starts a connection over an already established WebSocket.
+    ///
+    pub fn new(socket: WebSocket<S>) -> Self {
+        Self {
+            socket,
+            dispatcher: Dispatcher::new(),
+            next_id: 1,
+            events: VecDeque::new(),
+        }
+    }
+
+    ///
+    /// Calls the command `C`, and waits for its result.
+    ///
+    pub fn call<C: Command>(&mut self, params: C::Parameters) -> Result<C::Returns, Error> {
+        let result = self.call_raw(C::id(), serde_json::to_value(params)?, None)?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    ///
+    /// Calls a command by its method name, with raw parameters,
+    /// optionally on a (flattened) session.
+    ///
+    pub fn call_raw(
+        &mut self,
+        method: &str,
+        params: Value,
+        session_id: Option<SessionId>,
+    ) -> Result<Value, Error> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let frame = self
+            .dispatcher
+            .request(id, method.to_string(), params, session_id, ());
+
+        let result = self
+            .socket
+            .send(WsMessage::Text(frame))
+            .map_err(Error::from)
+            .and_then(|_| self.response());
+
+        // Forget a call that failed midway, so its late response
+        // isn't mistaken for the next call's.
+        self.dispatcher.cancel(id);
+
+        result
+    }
+
+    ///
+    /// Waits for the response of the pending call.
+    ///
+    fn response(&mut self) -> Result<Value, Error> {
+        loop {
+            match self.receive()? {
+                // Calls are made one at a time: any response answers the pending call.
+                Incoming::Response((), result) => return result,
+                Incoming::Notification(event) => {
+                    let detached = self.dispatcher.detached(&event);
+                    self.events.push_back(event);
+
+                    // The pending call was made on the detached session.
+                    if let Some((session_id, calls)) = detached {
+                        if !calls.is_empty() {
+                            return Err(Error::Detached(session_id));
+                        }
+                    }
+                }
+                Incoming::Ignored => {}
+            }
+        }
+    }
+
+    ///
+    /// Waits for the next event.
+    ///
+    pub fn next_event(&mut self) -> Result<RawNotification, Error> {
+        if let Some(event) = self.events.pop_front() {
+            return Ok(event);
+        }
+
+        loop {
+            if let Incoming::Notification(event) = self.receive()? {
+                return Ok(event);
+            }
+        }
+    }
+
+    ///
+    /// Iterates over incoming events of type `E`, skipping any other event,
+    /// until the connection is closed.
+    ///
+    pub fn events<E: Event>(
+        &mut self,
+    ) -> impl Iterator<Item = Result<Notification<E>, Error>> + '_ {
+        std::iter::from_fn(move || loop {
+            match self.next_event() {
+                Ok(event) if event.method == E::__id() => {
+                    return Some(event.parse().map_err(Error::from))
+                }
+                Ok(_) => continue,
+                Err(Error::ConnectionClosed) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+        })
+    }
+
+    ///
+    /// Closes the connection.
+    ///
+    pub fn close(mut self) -> Result<(), Error> {
+        self.socket.close(None)?;
+
+        // Wait for the other side to acknowledge.
+        loop {
+            match self.socket.read() {
+                Ok(_) => continue,
+                Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    ///
+    /// Reads the next frame, skipping anything but text, and malformed frames.
+    ///
+    fn receive(&mut self) -> Result<Incoming<()>, Error> {
+        loop {
+            match self.socket.read() {
+                Ok(WsMessage::Text(frame)) => match self.dispatcher.receive(&frame) {
+                    Ok(incoming) => return Ok(incoming),
+                    Err(_) => continue,
+                },
+                Ok(_) => continue,
+                Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                    return Err(Error::ConnectionClosed)
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{protocol::page, test_util};
+
+    ///
+    /// Starts a WebSocket server, which sends `before` ahead of answering
+    /// each request, and closes the connection after `requests` requests.
+    ///
+    fn mock_server(requests: usize, before: Vec<Value>) -> String {
+        test_util::serve_requests(requests, move |request| {
+            let response = json!({ "id": request["id"], "result": { "frameId": "main" } });
+            before.iter().cloned().chain([response]).collect()
+        })
+    }
+
+    #[test]
+    fn test_call_and_events() {
+        let loaded = json!({ "method": "Page.loadEventFired", "params": { "timestamp": 1.5 } });
+        let other =
+            json!({ "method": "Page.domContentEventFired", "params": { "timestamp": 1.0 } });
+        let url = mock_server(2, vec![other, loaded]);
+
+        let mut connection = Connection::connect(&url).unwrap();
+
+        for _ in 0..2 {
+            let returns = connection
+                .call::<page::Navigate>(page::NavigateParams {
+                    url: "https://example.com".to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+
+            assert_eq!(returns.frame_id, "main");
+        }
+
+        let events = connection
+            .events::<page::LoadEventFiredEvent>()
+            .map(|event| event.unwrap().params.timestamp)
+            .collect::<Vec<_>>();
+
+        assert_eq!(events, [1.5, 1.5]);
+    }
+}
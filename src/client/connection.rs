@@ -0,0 +1,795 @@
+//!
+//! Connections to a browser (or any other target), each driven by a task of
+//! its own which sends calls, matches responses with the call they answer,
+//! and broadcasts events to subscribers.
+//!
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use futures_util::{future, Sink, SinkExt, Stream, StreamExt};
+use serde_json::Value;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    sync::{broadcast, mpsc, oneshot},
+};
+use tokio_tungstenite::{tungstenite::Message as WsMessage, WebSocketStream};
+
+use super::{
+    dispatch::{Dispatcher, Incoming},
+    events,
+    middleware::{Middleware, Next, Request, Stack},
+    nested::{self, Nested},
+    Error, LagPolicy, Session,
+};
+use crate::{
+    protocol::target,
+    util::{CallId, Caller, Command, Domain, Event, Notification, RawNotification, SessionId},
+};
+
+///
+/// A bidirectional channel of text frames, such as a WebSocket.
+///
+pub trait Transport:
+    Stream<Item = Result<String, Error>> + Sink<String, Error = Error> + Send + Unpin + 'static
+{
+}
+
+impl<T> Transport for T where
+    T: Stream<Item = Result<String, Error>> + Sink<String, Error = Error> + Send + Unpin + 'static
+{
+}
+
+///
+/// Adapts a WebSocket into a [Transport] of text frames,
+/// skipping pings, pongs and binary frames.
+///
+pub fn websocket<S>(socket: WebSocketStream<S>) -> impl Transport
+where
+    S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    socket
+        .sink_map_err(Error::from)
+        .with(|frame| future::ready(Ok(WsMessage::Text(frame))))
+        .filter_map(|message| {
+            future::ready(match message {
+                Ok(WsMessage::Text(frame)) => Some(Ok(frame)),
+                Ok(_) => None,
+                Err(e) => Some(Err(Error::from(e))),
+            })
+        })
+}
+
+///
+/// Settings of a [Connection].
+///
+#[derive(Debug, Clone)]
+pub struct Options {
+    ///
+    /// Number of events buffered for subscribers which fall behind.
+    ///
+    pub event_capacity: usize,
+
+    ///
+    /// What subscribers do once they fall behind by more than `event_capacity`.
+    ///
+    pub lag_policy: LagPolicy,
+
+    ///
+    /// How long calls wait for their response by default, if not forever.
+    ///
+    pub call_timeout: Option<Duration>,
+
+    ///
+    /// Middleware around every call and event of the connection.
+    ///
+    pub middleware: Stack,
+}
+
+impl Options {
+    ///
+    /// Adds `middleware`, inside the middleware already there.
+    ///
+    pub fn layer(mut self, middleware: impl Middleware) -> Self {
+        self.middleware = self.middleware.layer(middleware);
+        self
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            event_capacity: 1024,
+            lag_policy: LagPolicy::default(),
+            call_timeout: None,
+            middleware: Stack::default(),
+        }
+    }
+}
+
+///
+/// A call waiting to be sent by the connection's task.
+///
+struct Call {
+    id: CallId,
+    method: String,
+    params: Value,
+    session_id: Option<SessionId>,
+    reply: oneshot::Sender<Result<Value, Error>>,
+}
+
+///
+/// Request to the connection's task.
+///
+enum Control {
+    Call(Call),
+
+    ///
+    /// The caller stopped waiting: forget the call.
+    ///
+    Cancel(CallId),
+
+    ///
+    /// Routes the events of a non-flat session (nested in
+    /// `Target.receivedMessageFromTarget` events, up to its
+    /// `Target.detachedFromTarget` event) to a channel of its own.
+    ///
+    Nest(SessionId, mpsc::UnboundedSender<RawNotification>),
+}
+
+///
+/// Cancels a call once dropped, unless it completed first.
+///
+struct Pending<'a> {
+    id: CallId,
+    control: &'a mpsc::UnboundedSender<Control>,
+    completed: bool,
+}
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        if !self.completed {
+            let _ = self.control.send(Control::Cancel(self.id));
+        }
+    }
+}
+
+///
+/// Handle to a connection, which can be cloned and shared between tasks.
+///
+/// The connection itself is driven by a background task (so it must be
+/// created inside a `tokio` runtime), which stops once every handle is dropped,
+/// or once the other side closes the connection. Pending calls and event
+/// subscriptions then fail with [Error::ConnectionClosed].
+///
+/// Dropping the future of a call cancels it: its response, if it ever
+/// comes, is ignored.
+///
+#[derive(Debug, Clone)]
+pub struct Connection {
+    control: mpsc::UnboundedSender<Control>,
+    next_id: Arc<AtomicU64>,
+    timeout: Option<Duration>,
+
+    // Weak, so subscribers see the end of the stream once the task stops.
+    events: broadcast::WeakSender<RawNotification>,
+    lag_policy: LagPolicy,
+    middleware: Stack,
+}
+
+impl Connection {
+    ///
+    /// Connects to a WebSocket debugger URL,
+    /// such as `ws://127.0.0.1:9222/devtools/browser/<id>`.
+    ///
+    pub async fn connect(url: &str) -> Result<Self, Error> {
+        Self::connect_with(url, Options::default()).await
+    }
+
+    ///
+    /// Connects to a WebSocket debugger URL, with non-default [Options].
+    ///
+    pub async fn connect_with(url: &str, options: Options) -> Result<Self, Error> {
+        let (socket, _) = tokio_tungstenite::connect_async(url).await?;
+        Ok(Self::with_options(websocket(socket), options))
+    }
+
+    ///
+    /// Starts a connection over any [Transport].
+    ///
+    pub fn new(transport: impl Transport) -> Self {
+        Self::with_options(transport, Options::default())
+    }
+
+    ///
+    /// Starts a connection over any [Transport], with non-default [Options].
+    ///
+    pub fn with_options(transport: impl Transport, options: Options) -> Self {
+        let (control, rx) = mpsc::unbounded_channel();
+        let (events, _) = broadcast::channel(options.event_capacity);
+
+        let connection = Self {
+            control,
+            next_id: Arc::new(AtomicU64::new(1)),
+            timeout: options.call_timeout,
+            events: events.downgrade(),
+            lag_policy: options.lag_policy,
+            middleware: options.middleware.clone(),
+        };
+
+        tokio::spawn(run(transport, rx, events, options.middleware));
+        connection
+    }
+
+    ///
+    /// Fails calls made through this handle which take longer than `timeout`
+    /// with [Error::Timeout], instead of [Options::call_timeout].
+    ///
+    /// Other handles to the same connection are unaffected:
+    /// ```no_run
+    /// # async fn run(connection: chrome_devtools_api::client::Connection) {
+    /// use std::time::Duration;
+    ///
+    /// use chrome_devtools_api::protocol::page;
+    ///
+    /// let result = connection
+    ///     .clone()
+    ///     .with_timeout(Duration::from_secs(5))
+    ///     .call::<page::Reload>(Default::default())
+    ///     .await;
+    /// # }
+    /// ```
+    ///
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    ///
+    /// Calls the command `C`, and waits for its result.
+    ///
+    pub async fn call<C: Command>(&self, params: C::Parameters) -> Result<C::Returns, Error> {
+        let result = self
+            .call_raw(C::id(), serde_json::to_value(params)?, None)
+            .await?;
+
+        Ok(serde_json::from_value(result)?)
+    }
+
+    ///
+    /// Calls a command by its method name, with raw parameters,
+    /// optionally on a (flattened) session.
+    ///
+    pub async fn call_raw(
+        &self,
+        method: &str,
+        params: Value,
+        session_id: Option<SessionId>,
+    ) -> Result<Value, Error> {
+        let request = Request {
+            method: method.to_string(),
+            params,
+            session_id,
+        };
+
+        Next::new(&self.middleware, self).run(request).await
+    }
+
+    ///
+    /// Sends a call past the middleware, and waits for its result.
+    ///
+    pub(super) async fn send(&self, request: Request) -> Result<Value, Error> {
+        let (id, result) = self.enqueue(request)?;
+        self.wait(id, result).await
+    }
+
+    ///
+    /// Waits for the `result` of queued call `id`, up to the call timeout
+    /// (cancelling the call if dropped before it completes).
+    ///
+    pub(super) async fn wait(
+        &self,
+        id: CallId,
+        result: oneshot::Receiver<Result<Value, Error>>,
+    ) -> Result<Value, Error> {
+        let mut pending = Pending {
+            id,
+            control: &self.control,
+            completed: false,
+        };
+
+        let result = match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, result)
+                .await
+                .map_err(|_| Error::Timeout(timeout))?,
+            None => result.await,
+        };
+
+        pending.completed = true;
+        result.unwrap_or(Err(Error::ConnectionClosed))
+    }
+
+    ///
+    /// Queues a call past the middleware, without waiting: returns its id,
+    /// and where its result will come from (see [wait](Self::wait)).
+    ///
+    pub(super) fn enqueue(
+        &self,
+        request: Request,
+    ) -> Result<(CallId, oneshot::Receiver<Result<Value, Error>>), Error> {
+        let Request {
+            method,
+            params,
+            session_id,
+        } = request;
+
+        let (reply, result) = oneshot::channel();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+        self.control
+            .send(Control::Call(Call {
+                id,
+                method,
+                params,
+                session_id,
+                reply,
+            }))
+            .map_err(|_| Error::ConnectionClosed)?;
+
+        Ok((id, result))
+    }
+
+    ///
+    /// Every event of non-flat session `session_id` received from now on, none of
+    /// which is ever dropped, until it's detached or the connection is closed.
+    ///
+    pub(super) fn nest(&self, session_id: SessionId) -> mpsc::UnboundedReceiver<RawNotification> {
+        let (events, rx) = mpsc::unbounded_channel();
+
+        // Once closed, `events` is dropped right away, ending `rx`.
+        let _ = self.control.send(Control::Nest(session_id, events));
+        rx
+    }
+
+    ///
+    /// Every event received from now on, whichever session it comes from,
+    /// until the connection is closed (with a final [Error::ConnectionClosed]).
+    ///
+    /// Each subscriber gets its own copy of every event, and those
+    /// falling behind are handled according to [Options::lag_policy].
+    ///
+    pub fn raw_events(
+        &self,
+    ) -> impl Stream<Item = Result<RawNotification, Error>> + Send + Unpin + 'static {
+        events::receive(
+            self.events.upgrade().map(|events| events.subscribe()),
+            self.lag_policy,
+        )
+    }
+
+    ///
+    /// Events of type `E` received from now on, whichever session they come from.
+    ///
+    pub fn subscribe<E: Event + Send + 'static>(
+        &self,
+    ) -> impl Stream<Item = Result<Notification<E>, Error>> + Send + Unpin + 'static {
+        events::typed(self.raw_events())
+    }
+
+    ///
+    /// Events of the domain `D` received from now on (as raw method and params),
+    /// whichever session they come from.
+    ///
+    pub fn subscribe_domain<D: Domain>(
+        &self,
+    ) -> impl Stream<Item = Result<RawNotification, Error>> + Send + Unpin + 'static {
+        events::of_domain::<D>(self.raw_events())
+    }
+
+    ///
+    /// Attaches to a target, returning a [Session] of its own
+    /// over this connection (with `flatten: true`).
+    ///
+    pub async fn attach(&self, target_id: impl Into<target::TargetId>) -> Result<Session, Error> {
+        let returns = self
+            .call::<target::AttachToTarget>(target::AttachToTargetParams {
+                target_id: target_id.into(),
+                flatten: Some(true),
+            })
+            .await?;
+
+        Ok(self.session(returns.session_id))
+    }
+
+    ///
+    /// Handle to an already attached (flattened) session.
+    ///
+    pub fn session(&self, session_id: impl Into<SessionId>) -> Session {
+        Session::new(self.clone(), session_id)
+    }
+
+    ///
+    /// Attaches to a target without flatten mode (with `flatten: false`),
+    /// for browsers which don't support it, returning a [Session] of its own
+    /// nested in this connection (see [nested](super::nested)).
+    ///
+    pub async fn attach_nested(
+        &self,
+        target_id: impl Into<target::TargetId>,
+    ) -> Result<Session, Error> {
+        let returns = self
+            .call::<target::AttachToTarget>(target::AttachToTargetParams {
+                target_id: target_id.into(),
+                flatten: Some(false),
+            })
+            .await?;
+
+        Ok(self.nested_session(returns.session_id))
+    }
+
+    ///
+    /// Handle to an already attached non-flat session, running a connection of its
+    /// own (with the same call timeout and lag policy) over a [Nested] transport.
+    ///
+    pub fn nested_session(&self, session_id: impl Into<SessionId>) -> Session {
+        let session_id = session_id.into();
+        let options = Options {
+            call_timeout: self.timeout,
+            lag_policy: self.lag_policy,
+            middleware: self.middleware.clone(),
+            ..Default::default()
+        };
+
+        let connection = Self::with_options(Nested::new(self, session_id.clone()), options);
+        Session::nested(connection, session_id, self.clone())
+    }
+}
+
+impl Caller for Connection {
+    type Error = Error;
+
+    async fn call_raw(&self, method: &'static str, params: Value) -> Result<Value, Error> {
+        Connection::call_raw(self, method, params, None).await
+    }
+}
+
+///
+/// Drives a connection: sends calls, routes responses back
+/// to their caller, and broadcasts events to subscribers.
+///
+async fn run(
+    mut transport: impl Transport,
+    mut control: mpsc::UnboundedReceiver<Control>,
+    events: broadcast::Sender<RawNotification>,
+    middleware: Stack,
+) {
+    let mut dispatcher = Dispatcher::new();
+    let mut nested = HashMap::<SessionId, mpsc::UnboundedSender<RawNotification>>::new();
+
+    loop {
+        tokio::select! {
+            request = control.recv() => match request {
+                Some(Control::Call(call)) => {
+                    let frame = dispatcher.request(
+                        call.id,
+                        call.method,
+                        call.params,
+                        call.session_id,
+                        call.reply,
+                    );
+
+                    if transport.send(frame).await.is_err() {
+                        break;
+                    }
+                }
+                Some(Control::Cancel(id)) => {
+                    dispatcher.cancel(id);
+                }
+                Some(Control::Nest(session_id, events)) => {
+                    nested.insert(session_id, events);
+                }
+                None => break,
+            },
+            frame = transport.next() => {
+                let Some(Ok(frame)) = frame else { break };
+
+                // Malformed frames are skipped.
+                match dispatcher.receive(&frame) {
+                    Ok(Incoming::Response(reply, result)) => {
+                        let _ = reply.send(result);
+                    }
+                    Ok(Incoming::Notification(event)) => {
+                        if let Some((session_id, replies)) = dispatcher.detached(&event) {
+                            for reply in replies {
+                                let _ = reply.send(Err(Error::Detached(session_id.clone())));
+                            }
+
+                            if let Some(events) = nested.remove(&session_id) {
+                                let _ = events.send(event.clone());
+                            }
+                        }
+
+                        // Unlike subscribers, nested sessions can't afford to lag.
+                        if let Some(session_id) = nested::recipient(&event) {
+                            if let Some(events) = nested.get(session_id) {
+                                if events.send(event.clone()).is_err() {
+                                    nested.remove(session_id);
+                                }
+                            }
+                        }
+
+                        // Nobody may be listening.
+                        if let Some(event) = middleware.event(event) {
+                            let _ = events.send(event);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    for reply in dispatcher.drain() {
+        let _ = reply.send(Err(Error::ConnectionClosed));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        protocol::{page, DomainClients},
+        test_util,
+        util::{ErrorCode, Nothing},
+    };
+
+    ///
+    /// Starts a WebSocket server answering every request with `respond`,
+    /// answering a batch of `batch` requests at once, in reverse order.
+    ///
+    fn mock_server(batch: usize, respond: fn(Value) -> Value) -> String {
+        let mut requests = Vec::new();
+
+        test_util::serve(move |request| {
+            requests.push(request);
+
+            match requests.len() == batch {
+                true => requests.drain(..).rev().map(respond).collect(),
+                false => Vec::new(),
+            }
+        })
+    }
+
+    fn navigate(url: &str) -> page::NavigateParams {
+        page::NavigateParams {
+            url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_call() {
+        let url = mock_server(1, |request| {
+            json!({
+                "id": request["id"],
+                "result": { "frameId": request["params"]["url"] },
+            })
+        });
+
+        let connection = Connection::connect(&url).await.unwrap();
+        let returns = connection
+            .call::<page::Navigate>(navigate("https://example.com"))
+            .await
+            .unwrap();
+
+        assert_eq!(returns.frame_id, "https://example.com");
+    }
+
+    #[tokio::test]
+    async fn test_domain_client() {
+        let url = mock_server(1, |request| {
+            json!({
+                "id": request["id"],
+                "result": { "frameId": request["params"]["referrer"] },
+            })
+        });
+
+        let connection = Connection::connect(&url).await.unwrap();
+        let returns = connection
+            .page()
+            .navigate("https://example.com")
+            .referrer("https://example.org")
+            .await
+            .unwrap();
+
+        assert_eq!(returns.frame_id, "https://example.org");
+    }
+
+    #[tokio::test]
+    async fn test_error() {
+        let url = mock_server(1, |request| {
+            json!({
+                "id": request["id"],
+                "error": { "code": -32000, "message": "Cannot navigate to invalid URL" },
+            })
+        });
+
+        let connection = Connection::connect(&url).await.unwrap();
+
+        match connection.call::<page::Navigate>(navigate("invalid")).await {
+            Err(Error::Protocol(error)) => {
+                assert_eq!(error.code, ErrorCode::ServerError);
+                assert_eq!(error.message, "Cannot navigate to invalid URL");
+            }
+            other => panic!("Expected a protocol error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_out_of_order() {
+        let url = mock_server(3, |request| {
+            json!({
+                "id": request["id"],
+                "result": { "frameId": request["params"]["url"] },
+            })
+        });
+
+        let connection = Connection::connect(&url).await.unwrap();
+        let calls = ["a", "b", "c"].map(|url| {
+            let connection = connection.clone();
+            async move { connection.call::<page::Navigate>(navigate(url)).await }
+        });
+
+        let [a, b, c] = calls;
+        let (a, b, c) = tokio::join!(a, b, c);
+
+        assert_eq!(a.unwrap().frame_id, "a");
+        assert_eq!(b.unwrap().frame_id, "b");
+        assert_eq!(c.unwrap().frame_id, "c");
+    }
+
+    ///
+    /// Starts a WebSocket server sending `events` ahead of answering
+    /// each request, and closing the connection after one request.
+    ///
+    fn events_server(events: Vec<Value>) -> String {
+        test_util::serve_requests(1, move |request| {
+            let response = json!({ "id": request["id"], "result": {} });
+            events.iter().cloned().chain([response]).collect()
+        })
+    }
+
+    fn loaded(timestamp: f64) -> Value {
+        json!({ "method": "Page.loadEventFired", "params": { "timestamp": timestamp } })
+    }
+
+    #[tokio::test]
+    async fn test_subscribe() {
+        let url = events_server(vec![
+            loaded(1.0),
+            json!({ "method": "Network.dataReceived", "params": {} }),
+            loaded(2.0),
+        ]);
+
+        let connection = Connection::connect(&url).await.unwrap();
+
+        let [first, second] = [(), ()].map(|_| connection.subscribe::<page::LoadEventFiredEvent>());
+        let page = connection.subscribe_domain::<page::PageDomain>();
+
+        connection.call::<page::Enable>(Nothing).await.unwrap();
+
+        for loads in [first, second] {
+            let mut loads = loads.collect::<Vec<_>>().await;
+
+            assert!(matches!(loads.pop(), Some(Err(Error::ConnectionClosed))));
+            assert_eq!(
+                loads
+                    .into_iter()
+                    .map(|load| load.unwrap().params.timestamp)
+                    .collect::<Vec<_>>(),
+                [1.0, 2.0]
+            );
+        }
+
+        let methods = page
+            .filter_map(|event| future::ready(event.ok()))
+            .map(|event| event.method)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(methods, ["Page.loadEventFired", "Page.loadEventFired"]);
+    }
+
+    #[tokio::test]
+    async fn test_lag_policy() {
+        let url = events_server((1..=4).map(|n| loaded(n as f64)).collect());
+
+        let options = Options {
+            event_capacity: 2,
+            lag_policy: LagPolicy::Report,
+            ..Default::default()
+        };
+
+        let connection = Connection::connect_with(&url, options).await.unwrap();
+        let loads = connection.subscribe::<page::LoadEventFiredEvent>();
+
+        connection.call::<page::Enable>(Nothing).await.unwrap();
+
+        let mut loads = loads.collect::<Vec<_>>().await;
+
+        assert!(matches!(loads.pop(), Some(Err(Error::ConnectionClosed))));
+        assert!(matches!(loads[0], Err(Error::Lagged(2))));
+        assert_eq!(
+            loads[1..]
+                .iter()
+                .map(|load| load.as_ref().unwrap().params.timestamp)
+                .collect::<Vec<_>>(),
+            [3.0, 4.0]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_closed() {
+        // Read the request, then hang up without answering.
+        let url = test_util::serve_requests(1, |_| Vec::new());
+
+        let connection = Connection::connect(&url).await.unwrap();
+        let mut events = connection.raw_events();
+
+        assert!(matches!(
+            connection.call::<page::Disable>(Nothing).await,
+            Err(Error::ConnectionClosed)
+        ));
+        assert!(matches!(
+            events.next().await,
+            Some(Err(Error::ConnectionClosed))
+        ));
+        assert!(events.next().await.is_none());
+
+        // Already closed.
+        assert!(matches!(
+            connection.raw_events().next().await,
+            Some(Err(Error::ConnectionClosed))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        // The first call is only answered once the second one is made, too late.
+        let url = mock_server(2, |request| {
+            json!({
+                "id": request["id"],
+                "result": { "frameId": request["params"]["url"] },
+            })
+        });
+
+        let connection = Connection::connect(&url).await.unwrap();
+        let timeout = Duration::from_millis(50);
+
+        match connection
+            .clone()
+            .with_timeout(timeout)
+            .call::<page::Navigate>(navigate("slow"))
+            .await
+        {
+            Err(Error::Timeout(after)) => assert_eq!(after, timeout),
+            other => panic!("Expected a timeout, got {other:?}"),
+        }
+
+        // The late response of the cancelled call is ignored.
+        let returns = connection
+            .call::<page::Navigate>(navigate("fast"))
+            .await
+            .unwrap();
+
+        assert_eq!(returns.frame_id, "fast");
+    }
+}
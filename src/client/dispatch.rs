@@ -0,0 +1,226 @@
+//!
+//! Bookkeeping of a client connection, independent of any I/O:
+//! keeps track of outgoing calls, matches incoming
+//! responses with the pending call they answer, and
+//! fails the pending calls of detached sessions.
+//!
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use super::Error;
+use crate::{
+    protocol::target,
+    util::{CallId, Event, Message, ProtocolError, RawNotification, RawRequest, SessionId},
+};
+
+///
+/// A classified incoming frame.
+///
+pub(crate) enum Incoming<P> {
+    ///
+    /// Response to the pending call `P`.
+    ///
+    Response(P, Result<Value, Error>),
+
+    Notification(RawNotification),
+
+    ///
+    /// A request, or a response to no pending call.
+    ///
+    Ignored,
+}
+
+///
+/// Pending calls of a connection, each with some state `P`
+/// (e.g. the channel to send the call's result through).
+///
+pub(crate) struct Dispatcher<P> {
+    pending: HashMap<CallId, (Option<SessionId>, P)>,
+}
+
+impl<P> Dispatcher<P> {
+    pub fn new() -> Self {
+        Self {
+            pending: HashMap::new(),
+        }
+    }
+
+    ///
+    /// Registers a new call, returning the frame to send.
+    ///
+    /// Ids are assigned by the caller (so they are known before the call
+    /// reaches the dispatcher), and must be unique among pending calls.
+    ///
+    pub fn request(
+        &mut self,
+        id: CallId,
+        method: String,
+        params: Value,
+        session_id: Option<SessionId>,
+        pending: P,
+    ) -> String {
+        self.pending.insert(id, (session_id.clone(), pending));
+
+        let request = RawRequest {
+            id,
+            method,
+            params: Some(params),
+            session_id,
+        };
+
+        // Every field is either a string, a number, or an already built `Value`.
+        serde_json::to_string(&request).expect("requests always serialize")
+    }
+
+    ///
+    /// Forgets a pending call, whose response (if any) will be ignored.
+    ///
+    pub fn cancel(&mut self, id: CallId) -> Option<P> {
+        self.pending.remove(&id).map(|(_, pending)| pending)
+    }
+
+    ///
+    /// Classifies an incoming frame, resolving the call it answers (if any).
+    ///
+    pub fn receive(&mut self, frame: &str) -> Result<Incoming<P>, Error> {
+        Ok(match serde_json::from_str(frame)? {
+            Message::Response(response) => match self.pending.remove(&response.id) {
+                Some((_, pending)) => Incoming::Response(
+                    pending,
+                    response.result.map_err(|error| {
+                        serde_json::from_value::<ProtocolError>(error)
+                            .map_or_else(Error::from, Error::from)
+                    }),
+                ),
+                None => Incoming::Ignored,
+            },
+            Message::Notification(notification) => Incoming::Notification(notification),
+            Message::Request(_) => Incoming::Ignored,
+        })
+    }
+
+    ///
+    /// Removes the pending calls of the session detached by `notification`,
+    /// if it is a `Target.detachedFromTarget` event: they won't be answered.
+    ///
+    pub fn detached(&mut self, notification: &RawNotification) -> Option<(SessionId, Vec<P>)> {
+        if notification.method != target::DetachedFromTargetEvent::__id() {
+            return None;
+        }
+
+        let session_id = notification
+            .params
+            .clone()
+            .and_then(|params| {
+                serde_json::from_value::<target::DetachedFromTargetEvent>(params).ok()
+            })?
+            .session_id;
+
+        let ids = self
+            .pending
+            .iter()
+            .filter(|(_, (session, _))| session.as_ref() == Some(&session_id))
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+
+        let pending = ids
+            .into_iter()
+            .filter_map(|id| self.pending.remove(&id))
+            .map(|(_, pending)| pending)
+            .collect();
+
+        Some((session_id, pending))
+    }
+
+    ///
+    /// Removes every pending call, e.g. once the connection is closed.
+    ///
+    #[cfg_attr(not(feature = "client"), allow(dead_code))]
+    pub fn drain(&mut self) -> impl Iterator<Item = P> + '_ {
+        self.pending.drain().map(|(_, (_, pending))| pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_dispatch() {
+        let mut dispatcher = Dispatcher::new();
+
+        let (first, second) = (1, 2);
+
+        dispatcher.request(first, "Page.enable".into(), json!({}), None, "first");
+        let frame = dispatcher.request(
+            second,
+            "Page.reload".into(),
+            json!({}),
+            Some("session".into()),
+            "second",
+        );
+
+        assert_eq!(
+            serde_json::from_str::<Value>(&frame).unwrap(),
+            json!({ "id": second, "method": "Page.reload", "params": {}, "sessionId": "session" })
+        );
+
+        let error = json!({ "id": second, "error": { "code": -32000, "message": "Failed" } });
+
+        match dispatcher.receive(&error.to_string()).unwrap() {
+            Incoming::Response("second", Err(Error::Protocol(error))) => {
+                assert_eq!(error.message, "Failed")
+            }
+            _ => panic!("Expected an error response"),
+        }
+
+        // Already answered.
+        assert!(matches!(
+            dispatcher.receive(&error.to_string()).unwrap(),
+            Incoming::Ignored
+        ));
+
+        let response = json!({ "id": first, "result": {} });
+
+        assert_eq!(dispatcher.cancel(first), Some("first"));
+        assert!(matches!(
+            dispatcher.receive(&response.to_string()).unwrap(),
+            Incoming::Ignored
+        ));
+
+        assert_eq!(dispatcher.drain().count(), 0);
+    }
+
+    #[test]
+    fn test_detached() {
+        let mut dispatcher = Dispatcher::new();
+
+        dispatcher.request(1, "Page.enable".into(), json!({}), Some("a".into()), "a");
+        dispatcher.request(2, "Page.enable".into(), json!({}), Some("b".into()), "b");
+        dispatcher.request(3, "Target.getTargets".into(), json!({}), None, "browser");
+
+        let detached = json!({
+            "method": "Target.detachedFromTarget",
+            "params": { "sessionId": "a", "targetId": "A" },
+        });
+
+        let Incoming::Notification(notification) =
+            dispatcher.receive(&detached.to_string()).unwrap()
+        else {
+            panic!("Expected a notification");
+        };
+
+        assert_eq!(
+            dispatcher.detached(&notification),
+            Some(("a".to_string(), vec!["a"]))
+        );
+
+        let mut remaining = dispatcher.drain().collect::<Vec<_>>();
+        remaining.sort();
+        assert_eq!(remaining, ["b", "browser"]);
+    }
+}
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use crate::util::{ProtocolError, SessionId};
+
+///
+/// Error of a call made through a client.
+///
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    ///
+    /// The command failed on the other side.
+    ///
+    #[error(transparent)]
+    Protocol(#[from] ProtocolError),
+
+    ///
+    /// A message could not be (de)serialized.
+    ///
+    #[error("Invalid message: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    WebSocket(Box<tungstenite::Error>),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    ///
+    /// The connection was closed before the call completed
+    /// (or while subscribed to events).
+    ///
+    #[error("Connection closed")]
+    ConnectionClosed,
+
+    ///
+    /// No response came within the call's timeout.
+    ///
+    #[error("Call timed out after {0:?}")]
+    Timeout(Duration),
+
+    ///
+    /// The session was detached from its target before the call completed.
+    ///
+    #[error("Session {0} detached")]
+    Detached(SessionId),
+
+    ///
+    /// An event subscription fell behind, and missed this many events.
+    ///
+    #[error("Missed {0} events")]
+    Lagged(u64),
+}
+
+impl From<tungstenite::Error> for Error {
+    fn from(error: tungstenite::Error) -> Self {
+        Self::WebSocket(Box::new(error))
+    }
+}
@@ -0,0 +1,95 @@
+//!
+//! Streams of incoming events, fanned out to every subscriber
+//! of a connection through a bounded broadcast buffer.
+//!
+
+use futures_util::{future, stream, stream::BoxStream, Stream, StreamExt};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use super::Error;
+use crate::util::{Domain, Event, Notification, RawNotification};
+
+///
+/// What a subscription does once it falls so far behind that
+/// the oldest events in its buffer are overwritten.
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LagPolicy {
+    ///
+    /// Silently skip the missed events.
+    ///
+    #[default]
+    Skip,
+
+    ///
+    /// Yield an [Error::Lagged] with the number of missed events, then go on.
+    ///
+    Report,
+
+    ///
+    /// End the stream.
+    ///
+    Close,
+}
+
+///
+/// Every event received by `receiver` (none if the connection is already closed),
+/// until the connection is closed, which is reported as [Error::ConnectionClosed].
+///
+pub(crate) fn receive(
+    receiver: Option<broadcast::Receiver<RawNotification>>,
+    lag_policy: LagPolicy,
+) -> BoxStream<'static, Result<RawNotification, Error>> {
+    let Some(receiver) = receiver else {
+        return stream::iter([Err(Error::ConnectionClosed)]).boxed();
+    };
+
+    stream::unfold(Some(receiver), move |receiver| async move {
+        let mut receiver = receiver?;
+
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((Ok(event), Some(receiver))),
+                Err(RecvError::Lagged(missed)) => match lag_policy {
+                    LagPolicy::Skip => continue,
+                    LagPolicy::Report => return Some((Err(Error::Lagged(missed)), Some(receiver))),
+                    LagPolicy::Close => return None,
+                },
+                Err(RecvError::Closed) => return Some((Err(Error::ConnectionClosed), None)),
+            }
+        }
+    })
+    .boxed()
+}
+
+///
+/// Events of type `E` among `events`.
+///
+pub(crate) fn typed<E: Event + Send + 'static>(
+    events: impl Stream<Item = Result<RawNotification, Error>> + Send + Unpin + 'static,
+) -> impl Stream<Item = Result<Notification<E>, Error>> + Send + Unpin + 'static {
+    events.filter_map(|event| {
+        future::ready(match event {
+            Ok(event) if event.method == E::__id() => Some(event.parse().map_err(Error::from)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+    })
+}
+
+///
+/// Events of the domain `D` among `events`.
+///
+pub(crate) fn of_domain<D: Domain>(
+    events: impl Stream<Item = Result<RawNotification, Error>> + Send + Unpin + 'static,
+) -> impl Stream<Item = Result<RawNotification, Error>> + Send + Unpin + 'static {
+    events.filter(|event| {
+        future::ready(match event {
+            Ok(event) => event
+                .method
+                .split_once('.')
+                .is_some_and(|(domain, _)| domain == D::name()),
+            Err(_) => true,
+        })
+    })
+}
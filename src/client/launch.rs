@@ -0,0 +1,500 @@
+//!
+//! Starting a browser to connect to:
+//! ```no_run
+//! # async fn run() -> Result<(), chrome_devtools_api::client::launch::Error> {
+//! use chrome_devtools_api::{client::launch::Launcher, protocol::browser};
+//!
+//! let browser = Launcher::new().headless(true).launch().await?;
+//!
+//! let version = browser
+//!     .connection()
+//!     .call::<browser::GetVersion>(Default::default())
+//!     .await?;
+//!
+//! // The browser is killed, and its temporary profile removed, once dropped.
+//! drop(browser);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Unless given one with [Launcher::executable], the executable is found with
+//! [find_executable]: the [EXECUTABLE_ENV] environment variable, then the usual
+//! names of Chrome and Chromium in `PATH`, then their usual install locations.
+//!
+
+use std::{
+    env,
+    ffi::OsString,
+    io::{self, BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+use tempfile::TempDir;
+use tokio::sync::oneshot;
+
+use super::{Connection, Options};
+
+///
+/// Environment variable overriding the executable to launch.
+///
+pub const EXECUTABLE_ENV: &str = "CHROME_PATH";
+
+///
+/// Names of the executable, looked up in `PATH`.
+///
+const EXECUTABLE_NAMES: [&str; 5] = [
+    "google-chrome-stable",
+    "google-chrome",
+    "chromium",
+    "chromium-browser",
+    "chrome",
+];
+
+///
+/// Usual install locations, when not in `PATH`.
+///
+const EXECUTABLE_PATHS: [&str; 5] = [
+    "/opt/google/chrome/chrome",
+    "/usr/lib/chromium/chromium",
+    "/usr/lib/chromium-browser/chromium-browser",
+    "/snap/bin/chromium",
+    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+];
+
+///
+/// Prefix of the line written to stderr once the debugging port is open.
+///
+const BANNER: &str = "DevTools listening on ";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("No Chrome or Chromium executable found (set {EXECUTABLE_ENV})")]
+    NotFound,
+
+    #[error("Could not start the browser: {0}")]
+    Io(#[from] io::Error),
+
+    ///
+    /// The browser exited without printing its debugger URL.
+    ///
+    #[error("The browser exited without a debugger URL: {0}")]
+    Exited(String),
+
+    ///
+    /// The browser didn't print its debugger URL in time.
+    ///
+    #[error("No debugger URL after {0:?}")]
+    Timeout(Duration),
+
+    #[error(transparent)]
+    Client(#[from] super::Error),
+}
+
+///
+/// How the browser is debugged.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Debugging {
+    ///
+    /// Over WebSocket, on a port (`0` for any free one).
+    ///
+    Port(u16),
+
+    ///
+    /// Over a [pipe](super::pipe), without opening any port.
+    ///
+    #[cfg(unix)]
+    Pipe,
+}
+
+///
+/// Finds a Chrome or Chromium executable, see the [module](self) docs.
+///
+pub fn find_executable() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(EXECUTABLE_ENV) {
+        return Some(path.into());
+    }
+
+    let in_path = env::var_os("PATH").into_iter().flat_map(|paths| {
+        env::split_paths(&paths)
+            .flat_map(|dir| EXECUTABLE_NAMES.map(|name| dir.join(name)))
+            .collect::<Vec<_>>()
+    });
+
+    in_path
+        .chain(EXECUTABLE_PATHS.map(PathBuf::from))
+        .find(|path| path.is_file())
+}
+
+///
+/// The debugger URL in a line of the browser's stderr, if it is the banner
+/// (e.g. `DevTools listening on ws://127.0.0.1:36775/devtools/browser/<id>`).
+///
+pub fn parse_banner(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix(BANNER)
+        .filter(|url| url.starts_with("ws://"))
+}
+
+///
+/// Builder of a browser process.
+///
+#[derive(Debug, Clone)]
+pub struct Launcher {
+    executable: Option<PathBuf>,
+    headless: bool,
+    debugging: Debugging,
+    user_data_dir: Option<PathBuf>,
+    args: Vec<OsString>,
+    timeout: Duration,
+    options: Options,
+}
+
+impl Default for Launcher {
+    fn default() -> Self {
+        Self {
+            executable: None,
+            headless: false,
+            debugging: Debugging::Port(0),
+            user_data_dir: None,
+            args: Vec::new(),
+            timeout: Duration::from_secs(30),
+            options: Options::default(),
+        }
+    }
+}
+
+impl Launcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Launches `executable`, instead of finding one.
+    ///
+    pub fn executable(mut self, executable: impl Into<PathBuf>) -> Self {
+        self.executable = Some(executable.into());
+        self
+    }
+
+    pub fn headless(mut self, headless: bool) -> Self {
+        self.headless = headless;
+        self
+    }
+
+    pub fn debugging(mut self, debugging: Debugging) -> Self {
+        self.debugging = debugging;
+        self
+    }
+
+    ///
+    /// Uses (and keeps) an existing profile,
+    /// instead of a temporary one removed along with the browser.
+    ///
+    pub fn user_data_dir(mut self, user_data_dir: impl Into<PathBuf>) -> Self {
+        self.user_data_dir = Some(user_data_dir.into());
+        self
+    }
+
+    ///
+    /// Appends an argument to the command line.
+    ///
+    pub fn arg(mut self, arg: impl Into<OsString>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args(mut self, args: impl IntoIterator<Item = impl Into<OsString>>) -> Self {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    ///
+    /// How long to wait for the browser to print its debugger URL.
+    ///
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    ///
+    /// Options of the connection to the browser.
+    ///
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    ///
+    /// Starts the browser, and connects to it.
+    ///
+    pub async fn launch(self) -> Result<Browser, Error> {
+        let executable = self
+            .executable
+            .clone()
+            .or_else(find_executable)
+            .ok_or(Error::NotFound)?;
+
+        let (user_data_dir, temp_dir) = match self.user_data_dir.clone() {
+            Some(dir) => (dir, None),
+            None => {
+                let dir = tempfile::Builder::new()
+                    .prefix("chrome-devtools-")
+                    .tempdir()?;
+                (dir.path().to_path_buf(), Some(dir))
+            }
+        };
+
+        let mut command = Command::new(executable);
+        command
+            .args(self.command_line(&user_data_dir))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null());
+
+        match self.debugging {
+            Debugging::Port(_) => {
+                command.stderr(Stdio::piped());
+
+                let mut process = Process {
+                    child: command.spawn()?,
+                    _temp_dir: temp_dir,
+                };
+
+                let url = debugger_url(&mut process.child, self.timeout).await?;
+                let connection = Connection::connect_with(&url, self.options).await?;
+
+                Ok(Browser {
+                    connection,
+                    websocket_url: Some(url),
+                    user_data_dir,
+                    process,
+                })
+            }
+            #[cfg(unix)]
+            Debugging::Pipe => {
+                let (child, pipe) = super::pipe::spawn(&mut command)?;
+
+                Ok(Browser {
+                    connection: Connection::with_options(pipe, self.options),
+                    websocket_url: None,
+                    user_data_dir,
+                    process: Process {
+                        child,
+                        _temp_dir: temp_dir,
+                    },
+                })
+            }
+        }
+    }
+
+    fn command_line(&self, user_data_dir: &Path) -> Vec<OsString> {
+        let mut user_data_dir_arg = OsString::from("--user-data-dir=");
+        user_data_dir_arg.push(user_data_dir);
+
+        let mut args = vec![
+            user_data_dir_arg,
+            "--no-first-run".into(),
+            "--no-default-browser-check".into(),
+        ];
+
+        match self.debugging {
+            Debugging::Port(port) => args.push(format!("--remote-debugging-port={port}").into()),
+            #[cfg(unix)]
+            Debugging::Pipe => args.push("--remote-debugging-pipe".into()),
+        }
+
+        if self.headless {
+            args.push("--headless".into());
+        }
+
+        args.extend(self.args.iter().cloned());
+        args
+    }
+}
+
+///
+/// Waits for the banner on the child's stderr, which keeps
+/// being read (and discarded) afterwards, so it never fills up.
+///
+async fn debugger_url(child: &mut Child, timeout: Duration) -> Result<String, Error> {
+    let stderr = child.stderr.take().expect("stderr is piped");
+    let (tx, rx) = oneshot::channel();
+
+    thread::spawn(move || {
+        let mut stderr = BufReader::new(stderr);
+        let mut output = Vec::new();
+
+        for line in stderr.by_ref().lines() {
+            let Ok(line) = line else { break };
+
+            if let Some(url) = parse_banner(&line) {
+                let _ = tx.send(Ok(url.to_string()));
+                io::copy(&mut stderr, &mut io::sink()).ok();
+                return;
+            }
+
+            output.push(line);
+        }
+
+        let _ = tx.send(Err(output.join("\n")));
+    });
+
+    match tokio::time::timeout(timeout, rx).await {
+        Ok(Ok(Ok(url))) => Ok(url),
+        Ok(Ok(Err(output))) => Err(Error::Exited(output)),
+        Ok(Err(_)) => Err(Error::Exited(String::new())),
+        Err(_) => Err(Error::Timeout(timeout)),
+    }
+}
+
+///
+/// A browser process, killed once dropped.
+///
+#[derive(Debug)]
+struct Process {
+    child: Child,
+
+    // Removed after the browser is gone.
+    _temp_dir: Option<TempDir>,
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+///
+/// A launched browser, and the connection to it.
+///
+/// Dropping it kills the browser, and removes its temporary profile.
+///
+#[derive(Debug)]
+pub struct Browser {
+    connection: Connection,
+    websocket_url: Option<String>,
+    user_data_dir: PathBuf,
+    process: Process,
+}
+
+impl Browser {
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    ///
+    /// The browser target's WebSocket URL, unless debugged over a pipe.
+    ///
+    pub fn websocket_url(&self) -> Option<&str> {
+        self.websocket_url.as_deref()
+    }
+
+    pub fn user_data_dir(&self) -> &Path {
+        &self.user_data_dir
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.process.child.id()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{protocol::browser, test_util};
+
+    #[test]
+    fn test_parse_banner() {
+        assert_eq!(
+            parse_banner("DevTools listening on ws://127.0.0.1:36775/devtools/browser/b0b4\n"),
+            Some("ws://127.0.0.1:36775/devtools/browser/b0b4")
+        );
+        assert_eq!(
+            parse_banner("[0101/000000.000:ERROR:gpu_init.cc(1)] Failed"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_launch() {
+        // Stands in for the browser's debugging port.
+        let url = test_util::serve(|request| {
+            vec![json!({
+                "id": request["id"],
+                "result": {
+                    "protocolVersion": "1.3",
+                    "product": "Fake/1.0",
+                    "revision": "",
+                    "userAgent": "",
+                    "jsVersion": "",
+                },
+            })]
+        });
+        let port = url.rsplit_once(':').unwrap().1;
+
+        // Records its arguments, prints the banner, and waits to be killed.
+        let dir = tempfile::tempdir().unwrap();
+        let (executable, args) = (dir.path().join("chrome"), dir.path().join("args"));
+
+        fs::write(
+            &executable,
+            format!(
+                "#!/bin/sh\necho \"$@\" > {}\necho 'Starting' >&2\necho 'DevTools listening on ws://127.0.0.1:{port}/devtools/browser/fake' >&2\nexec sleep 60\n",
+                args.display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&executable, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let browser = Launcher::new()
+            .executable(&executable)
+            .headless(true)
+            .arg("--mute-audio")
+            .launch()
+            .await
+            .unwrap();
+
+        let version = browser
+            .connection()
+            .call::<browser::GetVersion>(Default::default())
+            .await
+            .unwrap();
+
+        assert_eq!(version.product, "Fake/1.0");
+        assert_eq!(
+            browser.websocket_url(),
+            Some(format!("ws://127.0.0.1:{port}/devtools/browser/fake").as_str())
+        );
+
+        let args = fs::read_to_string(args).unwrap();
+        assert!(args.contains("--remote-debugging-port=0 --headless --mute-audio"));
+        assert!(args.contains(&format!(
+            "--user-data-dir={}",
+            browser.user_data_dir().display()
+        )));
+
+        let (pid, user_data_dir) = (browser.pid(), browser.user_data_dir().to_path_buf());
+        assert!(user_data_dir.is_dir());
+
+        drop(browser);
+
+        assert!(!user_data_dir.exists());
+        assert!(!Path::new(&format!("/proc/{pid}")).exists());
+    }
+
+    #[tokio::test]
+    async fn test_exited() {
+        match Launcher::new().executable("false").launch().await {
+            Err(Error::Exited(_)) => {}
+            other => panic!("Expected the browser to exit, got {other:?}"),
+        }
+    }
+}
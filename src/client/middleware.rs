@@ -0,0 +1,276 @@
+//!
+//! Middleware of a [Connection], around every call it makes
+//! (e.g. to log or time them, redact their params, or answer them in tests),
+//! and every event it receives (e.g. to rewrite or drop them).
+//!
+//! Each [Middleware] gets the [Request] first, and passes it on to the
+//! [Next] one (or not, short-circuiting the call) in the order of its [Stack]:
+//! ```no_run
+//! # async fn run() -> Result<(), chrome_devtools_api::client::Error> {
+//! use std::time::Instant;
+//!
+//! use chrome_devtools_api::client::{
+//!     middleware::{Middleware, Next, Request},
+//!     Connection, Options,
+//! };
+//! use futures_util::future::BoxFuture;
+//! use serde_json::Value;
+//!
+//! struct Latency;
+//!
+//! impl Middleware for Latency {
+//!     fn call<'a>(
+//!         &'a self,
+//!         request: Request,
+//!         next: Next<'a>,
+//!     ) -> BoxFuture<'a, Result<Value, chrome_devtools_api::client::Error>> {
+//!         Box::pin(async move {
+//!             let (method, start) = (request.method.clone(), Instant::now());
+//!             let result = next.run(request).await;
+//!
+//!             println!("{method} took {:?}", start.elapsed());
+//!             result
+//!         })
+//!     }
+//! }
+//!
+//! let options = Options::default().layer(Latency);
+//! let connection = Connection::connect_with("ws://127.0.0.1:9222/devtools/browser/...", options).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+
+use std::{fmt, sync::Arc};
+
+use futures_util::future::BoxFuture;
+use serde_json::Value;
+
+use super::{Connection, Error};
+use crate::util::{RawNotification, SessionId};
+
+///
+/// A call made through a [Connection] (or one of its sessions).
+///
+#[derive(Debug, Clone)]
+pub struct Request {
+    ///
+    /// Method name of the command (its [Command::id](crate::util::Command::id)).
+    ///
+    pub method: String,
+
+    pub params: Value,
+
+    ///
+    /// Session the call is made on, if any.
+    ///
+    pub session_id: Option<SessionId>,
+}
+
+///
+/// Hooks around the calls and events of a [Connection].
+///
+pub trait Middleware: Send + Sync + 'static {
+    ///
+    /// Handles a call: by default, passes it on unchanged to the `next` middleware
+    /// (and eventually the connection itself).
+    ///
+    fn call<'a>(&'a self, request: Request, next: Next<'a>) -> BoxFuture<'a, Result<Value, Error>> {
+        next.run(request)
+    }
+
+    ///
+    /// Handles a received event before subscribers get it,
+    /// which they don't if it returns `None`: by default, passes it on unchanged.
+    ///
+    fn event(&self, event: RawNotification) -> Option<RawNotification> {
+        Some(event)
+    }
+}
+
+///
+/// The rest of the middleware a [Request] goes through,
+/// ending with the connection sending it.
+///
+pub struct Next<'a> {
+    rest: &'a [Arc<dyn Middleware>],
+    connection: &'a Connection,
+}
+
+impl<'a> Next<'a> {
+    pub(super) fn new(stack: &'a Stack, connection: &'a Connection) -> Self {
+        Self {
+            rest: &stack.layers,
+            connection,
+        }
+    }
+
+    ///
+    /// Passes `request` on, and waits for its result.
+    ///
+    pub fn run(self, request: Request) -> BoxFuture<'a, Result<Value, Error>> {
+        match self.rest.split_first() {
+            Some((first, rest)) => first.call(
+                request,
+                Next {
+                    rest,
+                    connection: self.connection,
+                },
+            ),
+            None => Box::pin(self.connection.send(request)),
+        }
+    }
+}
+
+///
+/// Middleware of a [Connection], from the outermost
+/// (first to handle calls, last to handle events) to the innermost.
+///
+#[derive(Clone, Default)]
+pub struct Stack {
+    layers: Arc<[Arc<dyn Middleware>]>,
+}
+
+impl Stack {
+    ///
+    /// Adds `middleware`, inside the middleware already there.
+    ///
+    pub fn layer(self, middleware: impl Middleware) -> Self {
+        let layers = self.layers.iter().cloned();
+        let middleware: Arc<dyn Middleware> = Arc::new(middleware);
+
+        Self {
+            layers: layers.chain([middleware]).collect(),
+        }
+    }
+
+    ///
+    /// Passes `event` through every middleware, innermost first.
+    ///
+    pub(super) fn event(&self, event: RawNotification) -> Option<RawNotification> {
+        self.layers
+            .iter()
+            .rev()
+            .try_fold(event, |event, middleware| middleware.event(event))
+    }
+}
+
+impl fmt::Debug for Stack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Stack")
+            .field("layers", &self.layers.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use futures_util::StreamExt;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        client::Options,
+        protocol::page,
+        test_util,
+        util::{Command, Event},
+    };
+
+    ///
+    /// Starts a WebSocket server answering `Page.navigate` with its URL as frame id
+    /// (after emitting a `Page.domContentEventFired` and a `Page.loadEventFired`
+    /// event), and any other method with a "method not found" error.
+    ///
+    fn mock_server() -> String {
+        test_util::serve(|request| match request["method"].as_str().unwrap() {
+            "Page.navigate" => vec![
+                json!({ "method": "Page.domContentEventFired", "params": { "timestamp": 1.0 } }),
+                json!({ "method": "Page.loadEventFired", "params": { "timestamp": 2.0 } }),
+                json!({ "id": request["id"], "result": { "frameId": request["params"]["url"] } }),
+            ],
+            _ => vec![json!({
+                "id": request["id"],
+                "error": { "code": -32601, "message": "Method not found" },
+            })],
+        })
+    }
+
+    ///
+    /// Records the method of every call, and redirects navigations.
+    ///
+    #[derive(Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Middleware for Recorder {
+        fn call<'a>(
+            &'a self,
+            mut request: Request,
+            next: Next<'a>,
+        ) -> BoxFuture<'a, Result<Value, Error>> {
+            self.0.lock().unwrap().push(request.method.clone());
+
+            if request.method == page::Navigate::id() {
+                request.params["url"] = json!("https://example.org");
+            }
+
+            next.run(request)
+        }
+    }
+
+    ///
+    /// Answers `Page.reload` itself, and drops `Page.domContentEventFired` events.
+    ///
+    struct Stub;
+
+    impl Middleware for Stub {
+        fn call<'a>(
+            &'a self,
+            request: Request,
+            next: Next<'a>,
+        ) -> BoxFuture<'a, Result<Value, Error>> {
+            match request.method == page::Reload::id() {
+                true => Box::pin(async { Ok(json!({})) }),
+                false => next.run(request),
+            }
+        }
+
+        fn event(&self, event: RawNotification) -> Option<RawNotification> {
+            (event.method != page::DomContentEventFiredEvent::__id()).then_some(event)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware() {
+        let recorder = Recorder::default();
+        let methods = recorder.0.clone();
+
+        let options = Options::default().layer(recorder).layer(Stub);
+        let connection = Connection::connect_with(&mock_server(), options)
+            .await
+            .unwrap();
+
+        let mut events = connection.raw_events();
+
+        let returns = connection
+            .call::<page::Navigate>(page::NavigateParams {
+                url: "https://example.com".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(returns.frame_id, "https://example.org");
+
+        connection
+            .call::<page::Reload>(Default::default())
+            .await
+            .unwrap();
+
+        assert_eq!(*methods.lock().unwrap(), ["Page.navigate", "Page.reload"]);
+
+        let event = events.next().await.unwrap().unwrap();
+        assert_eq!(event.method, page::LoadEventFiredEvent::__id());
+    }
+}
@@ -0,0 +1,82 @@
+//!
+//! # Client
+//! Connections to a browser (or anything else speaking the protocol),
+//! sending [Command](crate::util::Command)s and routing their responses
+//! back to the caller:
+//! ```no_run
+//! # #[cfg(feature = "client")]
+//! # async fn run() -> Result<(), chrome_devtools_api::client::Error> {
+//! use chrome_devtools_api::{client::Connection, protocol::target};
+//!
+//! let connection = Connection::connect("ws://127.0.0.1:9222/devtools/browser/...").await?;
+//! let targets = connection.call::<target::GetTargets>(Default::default()).await?;
+//!
+//! for target in targets.target_infos {
+//!     println!("{}: {}", target.target_id, target.url);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [Connection] is async (with the `client` feature), and
+//! [blocking::Connection] is its synchronous counterpart
+//! (with the `blocking` feature).
+//!
+//! Both connect over WebSocket, and [Connection] can also run over any
+//! [Transport], such as [pipe::Pipe] (for `--remote-debugging-pipe`).
+//!
+//! Both [Connection] and [Session] are [Caller](crate::util::Caller)s, so
+//! commands can also be called through each domain's client, such as
+//! `connection.page().navigate(url).await`
+//! (see [DomainClients](crate::protocol::DomainClients)).
+//!
+//! Calls and events can be hooked into with [middleware].
+//!
+//! A browser to connect to can be started with [launch::Launcher].
+//!
+//! Targets attached to with [Connection::attach] share the connection,
+//! each through a [Session] of its own (or, for browsers without flatten mode,
+//! with [Connection::attach_nested], see [nested]).
+//!
+//! Events are received by subscribing to them, on a [Connection] or a [Session]:
+//! ```no_run
+//! # #[cfg(feature = "client")]
+//! # async fn run(connection: chrome_devtools_api::client::Connection) {
+//! use chrome_devtools_api::protocol::page;
+//! use futures_util::StreamExt;
+//!
+//! let mut loads = connection.subscribe::<page::LoadEventFiredEvent>();
+//!
+//! while let Some(Ok(load)) = loads.next().await {
+//!     println!("Loaded at {}", load.params.timestamp);
+//! }
+//! # }
+//! ```
+//!
+
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "client")]
+mod connection;
+mod dispatch;
+mod error;
+#[cfg(feature = "client")]
+mod events;
+#[cfg(feature = "client")]
+pub mod launch;
+#[cfg(feature = "client")]
+pub mod middleware;
+#[cfg(feature = "client")]
+pub mod nested;
+#[cfg(all(feature = "client", unix))]
+pub mod pipe;
+#[cfg(feature = "client")]
+mod session;
+
+#[cfg(feature = "client")]
+pub use connection::*;
+pub use error::*;
+#[cfg(feature = "client")]
+pub use events::LagPolicy;
+#[cfg(feature = "client")]
+pub use session::*;
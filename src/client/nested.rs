@@ -0,0 +1,348 @@
+//!
+//! Transport of a non-flat session (attached with `flatten: false`), for browsers
+//! without flatten mode: its messages are wrapped in `Target.sendMessageToTarget`
+//! calls over the parent connection, and come back as JSON strings in
+//! `Target.receivedMessageFromTarget` events.
+//!
+//! [Connection::attach_nested](super::Connection::attach_nested) runs a
+//! [Session](super::Session) over it, with the same API as a flattened one:
+//! ```no_run
+//! # async fn run() -> Result<(), chrome_devtools_api::client::Error> {
+//! use chrome_devtools_api::{client::Connection, protocol::page};
+//!
+//! let connection = Connection::connect("ws://127.0.0.1:9222/devtools/browser/...").await?;
+//! let session = connection.attach_nested("<target id>").await?;
+//!
+//! session.call::<page::Enable>(Default::default()).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::{sink, stream, stream::BoxStream, Sink, Stream, StreamExt};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+use super::{middleware::Request, session::is_detached, Connection, Error};
+use crate::{
+    protocol::target,
+    util::{Command, Event, ProtocolError, RawNotification, SessionId},
+};
+
+///
+/// A [Transport](super::Transport) of the messages of session `session_id`,
+/// nested in the messages of its parent connection.
+///
+/// The transport ends once the session is detached (after passing on the
+/// `Target.detachedFromTarget` event itself), or the parent connection is closed.
+///
+pub struct Nested {
+    stream: BoxStream<'static, Result<String, Error>>,
+    sink: Pin<Box<dyn Sink<String, Error = Error> + Send>>,
+}
+
+///
+/// What comes back to a nested session: its events, routed by the parent
+/// connection, and error responses to the messages it failed to send.
+///
+struct Inbox {
+    events: mpsc::UnboundedReceiver<RawNotification>,
+    failures: mpsc::UnboundedReceiver<String>,
+    session_id: SessionId,
+}
+
+impl Nested {
+    pub fn new(parent: &Connection, session_id: impl Into<SessionId>) -> Self {
+        let session_id = session_id.into();
+        let (failed, failures) = mpsc::unbounded_channel();
+
+        let inbox = Inbox {
+            events: parent.nest(session_id.clone()),
+            failures,
+            session_id: session_id.clone(),
+        };
+
+        let stream = stream::unfold(Some(inbox), |inbox| async move {
+            let mut inbox = inbox?;
+
+            loop {
+                let event = tokio::select! {
+                    Some(failure) = inbox.failures.recv() => {
+                        return Some((Ok(failure), Some(inbox)));
+                    }
+                    event = inbox.events.recv() => event?,
+                };
+
+                if is_detached(&event, &inbox.session_id) {
+                    let frame = serde_json::to_string(&event).map_err(Error::from);
+                    return Some((frame, None));
+                }
+
+                if let Some(message) = received(event, &inbox.session_id) {
+                    return Some((Ok(message), Some(inbox)));
+                }
+            }
+        })
+        .boxed();
+
+        // Only queued on the parent connection (in order), so a slow
+        // round-trip there doesn't hold up the session.
+        let sink = sink::unfold(
+            (parent.clone(), session_id, failed),
+            |(parent, session_id, failed), message: String| async move {
+                let id = serde_json::from_str::<Value>(&message)?.get("id").cloned();
+
+                // Deprecated in favor of flatten mode, which is not available here.
+                #[allow(deprecated)]
+                let params = target::SendMessageToTargetParams {
+                    message,
+                    session_id: Some(session_id.clone()),
+                    ..Default::default()
+                };
+
+                #[allow(deprecated)]
+                let request = Request {
+                    method: target::SendMessageToTarget::id().to_string(),
+                    params: serde_json::to_value(params)?,
+                    session_id: None,
+                };
+
+                let (call, result) = parent.enqueue(request)?;
+                let (caller, failures) = (parent.clone(), failed.clone());
+
+                tokio::spawn(async move {
+                    tokio::select! {
+                        result = caller.wait(call, result) => {
+                            // The message never made it: answer it with the error instead.
+                            if let (Err(error), Some(id)) = (result, id) {
+                                let error = match error {
+                                    Error::Protocol(error) => error,
+                                    error => ProtocolError::server_error(error.to_string()),
+                                };
+
+                                let response = json!({ "id": id, "error": error });
+                                let _ = failures.send(response.to_string());
+                            }
+                        }
+                        // The session is gone, and nobody is left to answer.
+                        _ = failures.closed() => {}
+                    }
+                });
+
+                Ok((parent, session_id, failed))
+            },
+        );
+
+        Self {
+            stream,
+            sink: Box::pin(sink),
+        }
+    }
+}
+
+///
+/// The session whose message is nested in `event`, if it is a
+/// `Target.receivedMessageFromTarget` event.
+///
+pub(super) fn recipient(event: &RawNotification) -> Option<&str> {
+    if event.method != target::ReceivedMessageFromTargetEvent::__id() {
+        return None;
+    }
+
+    event.params.as_ref()?.get("sessionId")?.as_str()
+}
+
+///
+/// The message nested in `event`, if it is a
+/// `Target.receivedMessageFromTarget` event of session `session_id`.
+///
+fn received(event: RawNotification, session_id: &str) -> Option<String> {
+    if event.method != target::ReceivedMessageFromTargetEvent::__id() {
+        return None;
+    }
+
+    serde_json::from_value::<target::ReceivedMessageFromTargetEvent>(event.params?)
+        .ok()
+        .filter(|received| received.session_id == session_id)
+        .map(|received| received.message)
+}
+
+impl Stream for Nested {
+    type Item = Result<String, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.stream.as_mut().poll_next(cx)
+    }
+}
+
+impl Sink<String> for Nested {
+    type Error = Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.sink.as_mut().poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, frame: String) -> Result<(), Error> {
+        self.sink.as_mut().start_send(frame)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.sink.as_mut().poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.sink.as_mut().poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        client::Options,
+        protocol::{page, runtime},
+        test_util,
+    };
+
+    ///
+    /// Starts a browser-like WebSocket server without flatten mode: attaching
+    /// to a target opens session `S1`, whose messages are nested in
+    /// `Target.sendMessageToTarget` calls. There, `Page.enable` emits an event
+    /// on both `S1` and `S2` before returning, and `Page.reload` detaches `S1`.
+    /// `Runtime.enable` floods the connection with events before returning,
+    /// and its `Target.sendMessageToTarget` call never does.
+    ///
+    fn mock_server() -> String {
+        let nested = |session: &str, message: Value| {
+            json!({
+                "method": "Target.receivedMessageFromTarget",
+                "params": { "sessionId": session, "message": message.to_string() },
+            })
+        };
+
+        test_util::serve(move |request| {
+            let (id, params) = (&request["id"], &request["params"]);
+
+            let mut frames = match request["method"].as_str().unwrap() {
+                "Target.attachToTarget" => {
+                    assert_eq!(params["flatten"], false);
+                    vec![json!({ "id": id, "result": { "sessionId": "S1" } })]
+                }
+                _ => vec![json!({ "id": id, "result": {} })],
+            };
+
+            if request["method"] == "Target.sendMessageToTarget" {
+                let session = params["sessionId"].as_str().unwrap();
+                let inner =
+                    serde_json::from_str::<Value>(params["message"].as_str().unwrap()).unwrap();
+
+                assert_eq!(inner.get("sessionId"), None);
+
+                let replies = match inner["method"].as_str().unwrap() {
+                    "Page.enable" => vec![
+                        nested(
+                            "S2",
+                            json!({ "method": "Page.domContentEventFired", "params": { "timestamp": 2.0 } }),
+                        ),
+                        nested(
+                            session,
+                            json!({ "method": "Page.domContentEventFired", "params": { "timestamp": 1.0 } }),
+                        ),
+                        nested(session, json!({ "id": inner["id"], "result": {} })),
+                    ],
+                    "Runtime.enable" => {
+                        frames.clear();
+
+                        let mut flood = vec![json!({ "method": "Page.frameResized" }); 1000];
+                        flood.push(nested(session, json!({ "id": inner["id"], "result": {} })));
+                        flood
+                    }
+                    _ => vec![json!({
+                        "method": "Target.detachedFromTarget",
+                        "params": { "sessionId": session },
+                    })],
+                };
+
+                frames.extend(replies);
+            }
+
+            frames
+        })
+    }
+
+    #[tokio::test]
+    async fn test_nested() {
+        let connection = Connection::connect(&mock_server()).await.unwrap();
+        let session = connection.attach_nested("T1").await.unwrap();
+
+        assert_eq!(session.id(), "S1");
+
+        let events = session.subscribe::<page::DomContentEventFiredEvent>();
+        session
+            .call::<page::Enable>(Default::default())
+            .await
+            .unwrap();
+
+        match session.call::<page::Reload>(Default::default()).await {
+            Err(Error::ConnectionClosed) => {}
+            other => panic!("Expected the session to be detached, got {other:?}"),
+        }
+
+        // Only its own event, up until it was detached.
+        let events = events
+            .map(|event| event.unwrap().params.timestamp)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(events, [1.0]);
+    }
+
+    #[tokio::test]
+    async fn test_flood() {
+        let options = Options {
+            event_capacity: 1,
+            ..Default::default()
+        };
+
+        let connection = Connection::connect_with(&mock_server(), options)
+            .await
+            .unwrap();
+        let session = connection.attach_nested("T1").await.unwrap();
+
+        // Neither lagging behind the events, nor waiting on the parent's call.
+        let call = session.call::<runtime::Enable>(Default::default());
+        tokio::time::timeout(Duration::from_secs(5), call)
+            .await
+            .expect("the nested call to complete")
+            .unwrap();
+
+        // The parent's call is still unanswered, but not waited on once the session is gone:
+        // both the session's task, and the one waiting on that call, end.
+        let tasks = || {
+            tokio::runtime::Handle::current()
+                .metrics()
+                .num_alive_tasks()
+        };
+        let before = tasks();
+        drop(session);
+
+        for _ in 0..100 {
+            if tasks() + 2 <= before {
+                break;
+            }
+
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        assert!(tasks() + 2 <= before);
+    }
+}
@@ -0,0 +1,243 @@
+//!
+//! Transport over a pair of pipes, as used by Chrome with
+//! `--remote-debugging-pipe`: it reads messages from file descriptor 3,
+//! writes messages to file descriptor 4, and delimits them with `\0`.
+//!
+//! This avoids opening a debugging port altogether:
+//! ```no_run
+//! # async fn run() -> Result<(), chrome_devtools_api::client::Error> {
+//! use std::process::Command;
+//!
+//! use chrome_devtools_api::{client::{pipe, Connection}, protocol::browser};
+//!
+//! let (_child, transport) = pipe::spawn(
+//!     Command::new("chromium").args(["--headless", "--remote-debugging-pipe"]),
+//! )?;
+//!
+//! let connection = Connection::new(transport);
+//! let version = connection.call::<browser::GetVersion>(Default::default()).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+
+use std::{
+    io,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+        unix::process::CommandExt,
+    },
+    pin::Pin,
+    process::{Child, Command},
+    task::{Context, Poll},
+};
+
+use futures_util::{Sink, Stream};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::unix::pipe::{Receiver, Sender},
+};
+use tokio_util::codec::{AnyDelimiterCodec, AnyDelimiterCodecError, FramedRead, FramedWrite};
+
+use super::Error;
+
+///
+/// File descriptor the child reads messages from.
+///
+pub const CHILD_READ_FD: RawFd = 3;
+
+///
+/// File descriptor the child writes messages to.
+///
+pub const CHILD_WRITE_FD: RawFd = 4;
+
+///
+/// Longest message read from a pipe, in bytes: past it, the stream fails
+/// with an [InvalidData](io::ErrorKind::InvalidData) error, rather than
+/// buffering the output of a child which never writes a `\0` without bound.
+///
+pub const MAX_MESSAGE_LENGTH: usize = 256 * 1024 * 1024;
+
+impl From<AnyDelimiterCodecError> for Error {
+    fn from(error: AnyDelimiterCodecError) -> Self {
+        match error {
+            AnyDelimiterCodecError::Io(e) => Error::Io(e),
+            e => Error::Io(io::Error::new(io::ErrorKind::InvalidData, e)),
+        }
+    }
+}
+
+fn codec(max_length: usize) -> AnyDelimiterCodec {
+    AnyDelimiterCodec::new_with_max_length(b"\0".to_vec(), b"\0".to_vec(), max_length)
+}
+
+///
+/// A [Transport](super::Transport) of `\0`-delimited messages,
+/// read from `R` and written to `W`.
+///
+pub struct Pipe<R, W> {
+    reader: FramedRead<R, AnyDelimiterCodec>,
+    writer: FramedWrite<W, AnyDelimiterCodec>,
+}
+
+impl<R: AsyncRead, W: AsyncWrite> Pipe<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        Self {
+            reader: FramedRead::new(reader, codec(MAX_MESSAGE_LENGTH)),
+            writer: FramedWrite::new(writer, codec(MAX_MESSAGE_LENGTH)),
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin, W: Unpin> Stream for Pipe<R, W> {
+    type Item = Result<String, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.reader).poll_next(cx).map(|frame| {
+            frame.map(|frame| {
+                String::from_utf8(frame?.to_vec())
+                    .map_err(|e| Error::Io(io::Error::new(io::ErrorKind::InvalidData, e)))
+            })
+        })
+    }
+}
+
+impl<R: Unpin, W: AsyncWrite + Unpin> Sink<String> for Pipe<R, W> {
+    type Error = Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Sink::<String>::poll_ready(Pin::new(&mut self.writer), cx).map_err(Error::from)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, frame: String) -> Result<(), Error> {
+        Sink::<String>::start_send(Pin::new(&mut self.writer), frame).map_err(Error::from)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Sink::<String>::poll_flush(Pin::new(&mut self.writer), cx).map_err(Error::from)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Sink::<String>::poll_close(Pin::new(&mut self.writer), cx).map_err(Error::from)
+    }
+}
+
+///
+/// Duplicates `fd` above the child's pipe file descriptors,
+/// so moving it to either of them can't clobber the other.
+///
+fn above_child_fds(fd: impl Into<OwnedFd>) -> io::Result<OwnedFd> {
+    let fd: OwnedFd = fd.into();
+
+    match unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_DUPFD_CLOEXEC, CHILD_WRITE_FD + 1) } {
+        -1 => Err(io::Error::last_os_error()),
+        dup => Ok(unsafe { OwnedFd::from_raw_fd(dup) }),
+    }
+}
+
+///
+/// Spawns `command` with its file descriptors 3 and 4 connected to a new [Pipe].
+///
+/// The command is expected to speak the protocol over them, such as
+/// Chrome started with `--remote-debugging-pipe`.
+///
+pub fn spawn(command: &mut Command) -> io::Result<(Child, Pipe<Receiver, Sender>)> {
+    let (child_reader, writer) = io::pipe()?;
+    let (reader, child_writer) = io::pipe()?;
+
+    let child_reader = above_child_fds(child_reader)?;
+    let child_writer = above_child_fds(child_writer)?;
+
+    let (from, to) = (child_reader.as_raw_fd(), child_writer.as_raw_fd());
+
+    // Only async-signal-safe calls in between fork and exec; `dup2` clears
+    // close-on-exec on the new descriptors, so the child inherits them.
+    unsafe {
+        command.pre_exec(move || {
+            for (fd, target) in [(from, CHILD_READ_FD), (to, CHILD_WRITE_FD)] {
+                if libc::dup2(fd, target) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+
+            Ok(())
+        });
+    }
+
+    let child = command.spawn()?;
+
+    // The child has its own copies now.
+    drop((child_reader, child_writer));
+
+    let pipe = Pipe::new(
+        Receiver::from_owned_fd(reader.into())?,
+        Sender::from_owned_fd(writer.into())?,
+    );
+
+    Ok((child, pipe))
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::{SinkExt, StreamExt};
+    use serde_json::{json, Value};
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+    use crate::client::Connection;
+
+    #[tokio::test]
+    async fn test_echo() {
+        let (mut child, mut pipe) = spawn(Command::new("sh").args(["-c", "cat <&3 >&4"])).unwrap();
+
+        pipe.send("first".to_string()).await.unwrap();
+        pipe.send("second".to_string()).await.unwrap();
+
+        assert_eq!(pipe.next().await.unwrap().unwrap(), "first");
+        assert_eq!(pipe.next().await.unwrap().unwrap(), "second");
+
+        drop(pipe);
+        child.wait().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connection() {
+        let (client, server) = tokio::io::duplex(1024);
+        let (reader, writer) = tokio::io::split(client);
+        let connection = Connection::new(Pipe::new(reader, writer));
+
+        // Answers every request with its own parameters.
+        let (reader, writer) = tokio::io::split(server);
+        let mut server = Pipe::new(reader, writer);
+
+        tokio::spawn(async move {
+            while let Some(Ok(frame)) = server.next().await {
+                let request = serde_json::from_str::<Value>(&frame).unwrap();
+                let response = json!({ "id": request["id"], "result": request["params"] });
+
+                server.send(response.to_string()).await.unwrap();
+            }
+        });
+
+        let result = connection
+            .call_raw("Echo.echo", json!({ "text": "hello" }), None)
+            .await
+            .unwrap();
+
+        assert_eq!(result, json!({ "text": "hello" }));
+    }
+
+    #[tokio::test]
+    async fn test_max_length() {
+        let (reader, mut writer) = tokio::io::duplex(64);
+        let mut messages = FramedRead::new(reader, codec(4));
+
+        writer.write_all(b"four\0fives").await.unwrap();
+
+        assert_eq!(&messages.next().await.unwrap().unwrap()[..], b"four");
+        assert!(matches!(
+            messages.next().await.unwrap(),
+            Err(AnyDelimiterCodecError::MaxChunkLengthExceeded)
+        ));
+    }
+}
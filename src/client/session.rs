@@ -0,0 +1,249 @@
+use std::time::Duration;
+
+use futures_util::{future, Stream, StreamExt};
+use serde_json::Value;
+
+use super::events;
+use super::{Connection, Error};
+use crate::{
+    protocol::target,
+    util::{Caller, Command, Domain, Event, Notification, RawNotification, SessionId},
+};
+
+///
+/// Handle to a session attached to a target: its calls are stamped with
+/// its `sessionId`, and it only receives its own responses and events.
+///
+/// Flattened sessions share the connection they were attached on, while
+/// non-flat ones (for browsers without flatten mode) run a connection of their
+/// own over a [Nested](super::nested::Nested) transport, with the same API.
+///
+/// Once the session is detached from its target (on `Target.detachedFromTarget`),
+/// its pending calls fail with [Error::Detached] (or [Error::ConnectionClosed],
+/// if non-flat).
+///
+/// ```no_run
+/// # async fn run() -> Result<(), chrome_devtools_api::client::Error> {
+/// use chrome_devtools_api::{client::Connection, protocol::page};
+///
+/// let connection = Connection::connect("ws://127.0.0.1:9222/devtools/browser/...").await?;
+/// let session = connection.attach("<target id>").await?;
+///
+/// session.call::<page::Enable>(Default::default()).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct Session {
+    connection: Connection,
+    id: SessionId,
+
+    // The connection a non-flat session is nested in.
+    parent: Option<Connection>,
+}
+
+impl Session {
+    pub fn new(connection: Connection, id: impl Into<SessionId>) -> Self {
+        Self {
+            connection,
+            id: id.into(),
+            parent: None,
+        }
+    }
+
+    ///
+    /// A non-flat session, whose `connection` runs over
+    /// a [Nested](super::nested::Nested) transport of `parent`.
+    ///
+    pub(super) fn nested(connection: Connection, id: SessionId, parent: Connection) -> Self {
+        Self {
+            connection,
+            id,
+            parent: Some(parent),
+        }
+    }
+
+    pub fn id(&self) -> &SessionId {
+        &self.id
+    }
+
+    ///
+    /// The connection this session's calls go through: the one it was
+    /// attached on if flattened, or its own if non-flat.
+    ///
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    ///
+    /// Fails calls made through this handle which take longer than `timeout`,
+    /// like [Connection::with_timeout].
+    ///
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.connection = self.connection.with_timeout(timeout);
+        self
+    }
+
+    ///
+    /// Calls the command `C` on this session, and waits for its result.
+    ///
+    pub async fn call<C: Command>(&self, params: C::Parameters) -> Result<C::Returns, Error> {
+        let result = self
+            .call_raw(C::id(), serde_json::to_value(params)?)
+            .await?;
+
+        Ok(serde_json::from_value(result)?)
+    }
+
+    ///
+    /// Calls a command on this session by its method name, with raw parameters.
+    ///
+    pub async fn call_raw(&self, method: &str, params: Value) -> Result<Value, Error> {
+        self.connection
+            .call_raw(method, params, self.session_id())
+            .await
+    }
+
+    ///
+    /// The `sessionId` of this session's messages over [Session::connection]:
+    /// none if non-flat, as the session has the connection to itself.
+    ///
+    fn session_id(&self) -> Option<SessionId> {
+        self.parent.is_none().then(|| self.id.clone())
+    }
+
+    ///
+    /// Events of this session received from now on, until
+    /// it is detached, or the connection is closed (with a final [Error::ConnectionClosed]).
+    ///
+    pub fn raw_events(
+        &self,
+    ) -> impl Stream<Item = Result<RawNotification, Error>> + Send + Unpin + 'static {
+        let (detached, own) = (self.id.clone(), self.session_id());
+
+        self.connection
+            .raw_events()
+            .take_while(move |event| {
+                future::ready(!matches!(event, Ok(event) if is_detached(event, &detached)))
+            })
+            .filter(move |event| {
+                future::ready(match event {
+                    Ok(event) => event.session_id == own,
+                    Err(_) => true,
+                })
+            })
+    }
+
+    ///
+    /// Events of type `E` of this session received from now on.
+    ///
+    pub fn subscribe<E: Event + Send + 'static>(
+        &self,
+    ) -> impl Stream<Item = Result<Notification<E>, Error>> + Send + Unpin + 'static {
+        events::typed(self.raw_events())
+    }
+
+    ///
+    /// Events of the domain `D` of this session received from now on
+    /// (as raw method and params).
+    ///
+    pub fn subscribe_domain<D: Domain>(
+        &self,
+    ) -> impl Stream<Item = Result<RawNotification, Error>> + Send + Unpin + 'static {
+        events::of_domain::<D>(self.raw_events())
+    }
+
+    ///
+    /// Detaches the session from its target.
+    ///
+    pub async fn detach(self) -> Result<(), Error> {
+        self.parent
+            .as_ref()
+            .unwrap_or(&self.connection)
+            .call::<target::DetachFromTarget>(target::DetachFromTargetParams {
+                session_id: Some(self.id),
+                ..Default::default()
+            })
+            .await
+            .map(drop)
+    }
+}
+
+impl Caller for Session {
+    type Error = Error;
+
+    async fn call_raw(&self, method: &'static str, params: Value) -> Result<Value, Error> {
+        Session::call_raw(self, method, params).await
+    }
+}
+
+pub(super) fn is_detached(event: &RawNotification, session_id: &str) -> bool {
+    event.method == target::DetachedFromTargetEvent::__id()
+        && event
+            .params
+            .as_ref()
+            .and_then(|params| params.get("sessionId"))
+            .is_some_and(|id| id == session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{protocol::page, test_util};
+
+    ///
+    /// Starts a browser-like WebSocket server: attaching to a target opens
+    /// session `S1`, `Page.enable` emits an event on both `S1` and `S2`
+    /// before returning, and `Page.reload` detaches its session instead.
+    ///
+    fn mock_server() -> String {
+        test_util::serve(|request| {
+            let (id, session) = (&request["id"], &request["sessionId"]);
+
+            match request["method"].as_str().unwrap() {
+                "Target.attachToTarget" => {
+                    vec![json!({ "id": id, "result": { "sessionId": "S1" } })]
+                }
+                "Page.enable" => vec![
+                    json!({ "method": "Page.domContentEventFired", "params": { "timestamp": 2.0 }, "sessionId": "S2" }),
+                    json!({ "method": "Page.domContentEventFired", "params": { "timestamp": 1.0 }, "sessionId": session }),
+                    json!({ "id": id, "result": {}, "sessionId": session }),
+                ],
+                _ => vec![json!({
+                    "method": "Target.detachedFromTarget",
+                    "params": { "sessionId": session },
+                })],
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_session() {
+        let connection = Connection::connect(&mock_server()).await.unwrap();
+        let session = connection.attach("T1").await.unwrap();
+
+        assert_eq!(session.id(), "S1");
+
+        let events = session.subscribe::<page::DomContentEventFiredEvent>();
+        session
+            .call::<page::Enable>(Default::default())
+            .await
+            .unwrap();
+
+        match session.call::<page::Reload>(Default::default()).await {
+            Err(Error::Detached(id)) => assert_eq!(id, "S1"),
+            other => panic!("Expected the session to be detached, got {other:?}"),
+        }
+
+        // Only its own event, up until it was detached.
+        let events = events
+            .map(|event| event.unwrap().params.timestamp)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(events, [1.0]);
+    }
+}
@@ -0,0 +1,274 @@
+//!
+//! A minimal blocking HTTP/1.1 client for the `/json` endpoints of a browser:
+//! a request per connection, whose response body is delimited by its
+//! `Content-Length` or chunked.
+//!
+
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use serde::de::DeserializeOwned;
+
+use super::{BrowserVersion, Error, TargetDescription};
+
+///
+/// Client of a browser's HTTP endpoints, over plain `std::net`
+/// (they are only ever served over HTTP/1.1, without TLS).
+///
+#[derive(Debug, Clone)]
+pub struct Client {
+    host: String,
+    timeout: Option<Duration>,
+}
+
+impl Client {
+    ///
+    /// Client of the endpoints served on `host` (e.g. `127.0.0.1:9222`).
+    ///
+    pub fn new(host: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            timeout: None,
+        }
+    }
+
+    ///
+    /// Fails requests which take longer than `timeout` to connect, or to read.
+    ///
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn version(&self) -> Result<BrowserVersion, Error> {
+        self.json("GET", "/json/version")
+    }
+
+    pub fn list(&self) -> Result<Vec<TargetDescription>, Error> {
+        self.json("GET", "/json/list")
+    }
+
+    ///
+    /// Opens a new tab, navigated to `url` (or `about:blank`).
+    ///
+    pub fn new_target(&self, url: Option<&str>) -> Result<TargetDescription, Error> {
+        let path = match url {
+            Some(url) => format!("/json/new?{}", encode_query(url)),
+            None => "/json/new".to_string(),
+        };
+
+        // Recent versions of Chrome refuse to open tabs on a GET.
+        self.json("PUT", &path)
+    }
+
+    pub fn activate(&self, id: &str) -> Result<(), Error> {
+        self.request("GET", &format!("/json/activate/{id}"))
+            .map(drop)
+    }
+
+    pub fn close(&self, id: &str) -> Result<(), Error> {
+        self.request("GET", &format!("/json/close/{id}")).map(drop)
+    }
+
+    ///
+    /// The protocol definition supported by the browser.
+    ///
+    pub fn protocol(&self) -> Result<serde_json::Value, Error> {
+        self.json("GET", "/json/protocol")
+    }
+
+    fn json<T: DeserializeOwned>(&self, method: &str, path: &str) -> Result<T, Error> {
+        Ok(serde_json::from_slice(&self.request(method, path)?)?)
+    }
+
+    fn request(&self, method: &str, path: &str) -> Result<Vec<u8>, Error> {
+        let mut stream = match self.timeout {
+            Some(timeout) => {
+                let addr =
+                    self.host.to_socket_addrs()?.next().ok_or_else(|| {
+                        Error::InvalidResponse(format!("Unknown host {}", self.host))
+                    })?;
+                TcpStream::connect_timeout(&addr, timeout)?
+            }
+            None => TcpStream::connect(&self.host)?,
+        };
+
+        stream.set_read_timeout(self.timeout)?;
+        stream.write_all(request(method, &self.host, path).as_bytes())?;
+
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw)?;
+
+        response(&raw)
+    }
+}
+
+///
+/// Percent-encodes anything but the characters allowed in a URL.
+///
+fn encode_query(url: &str) -> String {
+    url.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => (b as char).to_string(),
+            b'-' | b'.' | b'_' | b'~' | b':' | b'/' | b'?' | b'[' | b']' | b'@' | b'!' | b'$'
+            | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'=' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+///
+/// A bodiless request, asking the server to close the connection after responding.
+///
+fn request(method: &str, host: &str, path: &str) -> String {
+    format!("{method} {path} HTTP/1.1\r\nHost: {host}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+}
+
+///
+/// Body of a (complete) response, if successful,
+/// delimited by its `Content-Length` or chunked.
+///
+fn response(raw: &[u8]) -> Result<Vec<u8>, Error> {
+    let invalid = |reason: &str| Error::InvalidResponse(reason.to_string());
+
+    let split = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| invalid("missing end of headers"))?;
+
+    let head = std::str::from_utf8(&raw[..split]).map_err(|_| invalid("non UTF-8 headers"))?;
+    let mut body = raw[split + 4..].to_vec();
+
+    let mut lines = head.split("\r\n");
+
+    let status = lines
+        .next()
+        .and_then(|line| line.split(' ').nth(1))
+        .and_then(|status| status.parse::<u16>().ok())
+        .ok_or_else(|| invalid("missing status"))?;
+
+    for (name, value) in lines.filter_map(|line| line.split_once(':')) {
+        if name.eq_ignore_ascii_case("content-length") {
+            let len = value
+                .trim()
+                .parse()
+                .map_err(|_| invalid("invalid Content-Length"))?;
+            body.truncate(len);
+        } else if name.eq_ignore_ascii_case("transfer-encoding")
+            && value.to_ascii_lowercase().contains("chunked")
+        {
+            body = dechunk(&body).ok_or_else(|| invalid("invalid chunked body"))?;
+        }
+    }
+
+    match status {
+        200..=299 => Ok(body),
+        status => Err(Error::Status {
+            status,
+            body: String::from_utf8_lossy(&body).into_owned(),
+        }),
+    }
+}
+
+///
+/// Decodes a chunked body, ignoring any chunk extensions and trailers.
+///
+fn dechunk(mut raw: &[u8]) -> Option<Vec<u8>> {
+    let mut body = Vec::new();
+
+    loop {
+        let end = raw.windows(2).position(|w| w == b"\r\n")?;
+        let size = std::str::from_utf8(&raw[..end]).ok()?;
+        let size = size.split(';').next()?.trim();
+        let size = usize::from_str_radix(size, 16).ok()?;
+
+        raw = &raw[end + 2..];
+
+        if size == 0 {
+            return Some(body);
+        }
+
+        body.extend_from_slice(raw.get(..size)?);
+        raw = raw.get(size + 2..)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::test_util;
+
+    ///
+    /// Serves a single request with `response`, returning the host
+    /// and a handle to the request received.
+    ///
+    fn mock_server(response: &'static str) -> (String, thread::JoinHandle<String>) {
+        let (addr, request) = test_util::accept(move |mut stream| {
+            let mut request = [0; 1024];
+            let len = stream.read(&mut request).unwrap();
+
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8_lossy(&request[..len]).into_owned()
+        });
+
+        (addr.to_string(), request)
+    }
+
+    #[test]
+    fn test_version() {
+        let (host, request) = mock_server(concat!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json; charset=UTF-8\r\n\r\n",
+            r#"{"Browser":"Chrome/120.0.6099.109","Protocol-Version":"1.3","User-Agent":"Mozilla/5.0","webSocketDebuggerUrl":"ws://host/devtools/browser/id"}"#,
+        ));
+
+        let version = Client::new(host).version().unwrap();
+
+        assert!(request
+            .join()
+            .unwrap()
+            .starts_with("GET /json/version HTTP/1.1\r\n"));
+        assert_eq!(version.browser, "Chrome/120.0.6099.109");
+        assert_eq!(
+            version.web_socket_debugger_url.as_deref(),
+            Some("ws://host/devtools/browser/id")
+        );
+    }
+
+    #[test]
+    fn test_new_target() {
+        let (host, request) =
+            mock_server("HTTP/1.1 500 Internal Server Error\r\nContent-Length: 6\r\n\r\nFailed");
+
+        match Client::new(host).new_target(Some("https://example.com/a b#c")) {
+            Err(Error::Status { status: 500, body }) => assert_eq!(body, "Failed"),
+            other => panic!("Expected an HTTP error, got {other:?}"),
+        }
+
+        assert!(request
+            .join()
+            .unwrap()
+            .starts_with("PUT /json/new?https://example.com/a%20b%23c HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn test_chunked() {
+        let (host, _) = mock_server(concat!(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n",
+            "6;ext=1\r\n[{\"id\"\r\n",
+            "33\r\n:\"1\",\"type\":\"page\",\"title\":\"\",\"url\":\"about:blank\"}]\r\n",
+            "0\r\n\r\n",
+        ));
+
+        let targets = Client::new(host).list().unwrap();
+
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].url, "about:blank");
+    }
+}
@@ -0,0 +1,242 @@
+//!
+//! # Discovery
+//! Finding the WebSocket debugger URL of a running browser, either
+//! through its HTTP endpoints (with [Client]):
+//! * `/json/version`: [BrowserVersion]
+//! * `/json/list`: a [TargetDescription] per target
+//! * `/json/new?{url}`: the new target's [TargetDescription]
+//! * `/json/activate/{id}` and `/json/close/{id}`
+//! * `/json/protocol`: the protocol definition
+//!
+//! or through the `DevToolsActivePort` file it writes in its
+//! user data directory (with [ActivePort]):
+//! ```no_run
+//! use chrome_devtools_api::discovery::{ActivePort, Client};
+//!
+//! let port = ActivePort::read("/tmp/profile")?;
+//! let url = port.websocket_url("127.0.0.1");
+//!
+//! let targets = Client::new(format!("127.0.0.1:{}", port.port)).list()?;
+//! # Ok::<(), chrome_devtools_api::discovery::Error>(())
+//! ```
+//!
+
+use std::{fs, io, path::Path, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+mod http;
+pub use http::*;
+
+///
+/// Name of the file a browser writes its debugging port to,
+/// in its user data directory.
+///
+pub const ACTIVE_PORT_FILE: &str = "DevToolsActivePort";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error("Invalid response: {0}")]
+    Json(#[from] serde_json::Error),
+
+    ///
+    /// The endpoint answered with an unsuccessful status.
+    ///
+    #[error("HTTP {status}: {body}")]
+    Status { status: u16, body: String },
+
+    #[error("Invalid HTTP response: {0}")]
+    InvalidResponse(String),
+
+    #[error("Invalid {ACTIVE_PORT_FILE} file: {0}")]
+    InvalidActivePort(String),
+}
+
+///
+/// Response of `/json/version`.
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BrowserVersion {
+    ///
+    /// Product name and version (e.g. `Chrome/120.0.6099.109`).
+    ///
+    #[serde(rename = "Browser")]
+    pub browser: String,
+
+    #[serde(rename = "Protocol-Version")]
+    pub protocol_version: String,
+
+    #[serde(rename = "User-Agent")]
+    pub user_agent: String,
+
+    #[serde(
+        rename = "V8-Version",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub v8_version: Option<String>,
+
+    #[serde(
+        rename = "WebKit-Version",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub webkit_version: Option<String>,
+
+    ///
+    /// Package name, on Android only.
+    ///
+    #[serde(
+        rename = "Android-Package",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub android_package: Option<String>,
+
+    ///
+    /// URL of the browser target, missing if another client is attached to it.
+    ///
+    #[serde(
+        rename = "webSocketDebuggerUrl",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub web_socket_debugger_url: Option<String>,
+}
+
+///
+/// A target, as listed by `/json/list` (or created by `/json/new`).
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetDescription {
+    pub id: String,
+
+    ///
+    /// Type of target (e.g. `page`, `service_worker`).
+    ///
+    #[serde(rename = "type")]
+    pub type_: String,
+
+    pub title: String,
+
+    pub url: String,
+
+    #[serde(default)]
+    pub description: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub devtools_frontend_url: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub favicon_url: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+
+    ///
+    /// URL of the target, missing if another client is attached to it.
+    ///
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub web_socket_debugger_url: Option<String>,
+}
+
+///
+/// Contents of the `DevToolsActivePort` file: the debugging port
+/// on the first line, and the browser target's path on the second.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActivePort {
+    pub port: u16,
+
+    ///
+    /// Path of the browser target (e.g. `/devtools/browser/<id>`).
+    ///
+    pub path: String,
+}
+
+impl ActivePort {
+    ///
+    /// Reads the `DevToolsActivePort` file in `user_data_dir`.
+    ///
+    pub fn read(user_data_dir: impl AsRef<Path>) -> Result<Self, Error> {
+        fs::read_to_string(user_data_dir.as_ref().join(ACTIVE_PORT_FILE))?.parse()
+    }
+
+    ///
+    /// WebSocket URL of the browser target, on `host`.
+    ///
+    pub fn websocket_url(&self, host: &str) -> String {
+        format!("ws://{host}:{}{}", self.port, self.path)
+    }
+}
+
+impl FromStr for ActivePort {
+    type Err = Error;
+
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        let mut lines = contents.lines().map(str::trim);
+
+        let port = lines
+            .next()
+            .and_then(|port| port.parse().ok())
+            .ok_or_else(|| Error::InvalidActivePort("expected a port on the first line".into()))?;
+
+        let path = lines
+            .next()
+            .filter(|path| path.starts_with('/'))
+            .ok_or_else(|| Error::InvalidActivePort("expected a path on the second line".into()))?;
+
+        Ok(Self {
+            port,
+            path: path.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_active_port() {
+        let port: ActivePort = "9222\n/devtools/browser/b0b4\n".parse().unwrap();
+
+        assert_eq!(port.port, 9222);
+        assert_eq!(
+            port.websocket_url("127.0.0.1"),
+            "ws://127.0.0.1:9222/devtools/browser/b0b4"
+        );
+
+        assert!("".parse::<ActivePort>().is_err());
+        assert!("9222".parse::<ActivePort>().is_err());
+        assert!("port\n/devtools/browser/b0b4"
+            .parse::<ActivePort>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_list() {
+        let list = r#"[{
+            "description": "",
+            "devtoolsFrontendUrl": "/devtools/inspector.html?ws=127.0.0.1:9222/devtools/page/A1",
+            "id": "A1",
+            "title": "about:blank",
+            "type": "page",
+            "url": "about:blank",
+            "webSocketDebuggerUrl": "ws://127.0.0.1:9222/devtools/page/A1"
+        }]"#;
+
+        let targets: Vec<TargetDescription> = serde_json::from_str(list).unwrap();
+
+        assert_eq!(targets[0].type_, "page");
+        assert_eq!(targets[0].parent_id, None);
+        assert_eq!(
+            targets[0].web_socket_debugger_url.as_deref(),
+            Some("ws://127.0.0.1:9222/devtools/page/A1")
+        );
+    }
+}
@@ -8,6 +8,24 @@
 //! By default, this crate uses the `/macros/test` protocol files as a source.
 //! You can optionally try to fetch the latest tip-of-tree protcol from the [DevTools GitHub](https://github.com/ChromeDevTools/devtools-protocol/tree/master)
 //! this is not guaranteed to build however.
+//!
+//! ### `cbor`
+//! Adds [util::cbor], to encode and decode messages as CBOR
+//! (as used by Chrome with `--remote-debugging-pipe=cbor`).
+//!
+//! ### `client`
+//! Adds [client::Connection], an async (`tokio`) client
+//! connecting to a browser's WebSocket debugger URL,
+//! and [client::launch], to start a browser to connect to.
+//!
+//! ### `blocking`
+//! Adds [client::blocking::Connection], a synchronous client
+//! (over `std::net`), for when an async runtime is overkill.
+//!
+//! ### `server`
+//! Adds [server::Listener], serving targets to DevTools over WebSocket
+//! (along with the `/json` discovery endpoints), on top of [server::Router],
+//! and [server::Emitter], sending them events.
 //! 
 //! ## Usage
 //! It's pretty much [`serde`](https://docs.rs/serde/1.0.183/serde/) and [`serde_json`](https://docs.rs/serde_json/1.0.104/serde_json/) all the way down.
@@ -19,11 +37,113 @@
 //! use chrome_devtools_api::util::Command;
 //! ```
 //! 
+//! ### Dispatching by method name
+//! [protocol::MethodCall] has a variant for every command's parameters,
+//! and is (de)serialized from a `{ "method": "...", "params": { ... } }` object:
+//! ```
+//! use chrome_devtools_api::protocol::MethodCall;
+//!
+//! let raw = r#"{ "method": "Page.navigate", "params": { "url": "https://example.com" } }"#;
+//!
+//! let call: MethodCall = serde_json::from_str(raw).unwrap();
+//! assert_eq!(serde_json::to_value(&call).unwrap()["method"], "Page.navigate");
+//!
+//! match call {
+//!     MethodCall::Page_Navigate(params) => assert_eq!(params.url, "https://example.com"),
+//!     _ => unreachable!(),
+//! }
+//! ```
+//! 
+//! Likewise, incoming events can be deserialized into [protocol::AnyEvent],
+//! which keeps unknown events as raw method/params pairs:
+//! ```
+//! use chrome_devtools_api::protocol::AnyEvent;
+//!
+//! let raw = r#"{ "method": "Page.loadEventFired", "params": { "timestamp": 1.5 } }"#;
+//! assert!(matches!(serde_json::from_str(raw).unwrap(), AnyEvent::Page_LoadEventFired(e) if e.timestamp == 1.5));
+//!
+//! let raw = r#"{ "method": "Custom.event", "params": { "a": 1 } }"#;
+//! assert!(matches!(serde_json::from_str(raw).unwrap(), AnyEvent::Unknown { method, .. } if method == "Custom.event"));
+//! ```
+//! 
+//! ### Implementing a server
+//! Each domain also has a handler trait (e.g. [protocol::runtime::RuntimeHandler]),
+//! with a method per command, for servers implementing the protocol.
+//! Only the supported commands need to be implemented, as the others
+//! fail with `-32601` ("method not found"):
+//! ```
+//! use chrome_devtools_api::{protocol::runtime, util::{ErrorCode, ProtocolError}};
+//!
+//! struct Runtime;
+//!
+//! impl runtime::RuntimeHandler<()> for Runtime {
+//!     async fn evaluate(
+//!         &mut self,
+//!         _: &mut (),
+//!         params: runtime::EvaluateParams,
+//!     ) -> Result<runtime::EvaluateReturns, ProtocolError> {
+//!         Ok(runtime::EvaluateReturns {
+//!             result: runtime::RemoteObject {
+//!                 value: Some(params.expression.into()),
+//!                 ..Default::default()
+//!             },
+//!             ..Default::default()
+//!         })
+//!     }
+//! }
+//!
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! use runtime::RuntimeHandler;
+//!
+//! let params = runtime::EvaluateParams {
+//!     expression: "1 + 1".to_string(),
+//!     ..Default::default()
+//! };
+//!
+//! let returns = Runtime.evaluate(&mut (), params).await.unwrap();
+//! assert_eq!(returns.result.value, Some("1 + 1".into()));
+//!
+//! let error = Runtime.enable(&mut ()).await.unwrap_err();
+//! assert_eq!(error.code, ErrorCode::MethodNotFound);
+//! # });
+//! ```
+//!
+//! A [server::Router] then routes incoming frames to each domain's handler.
+//! 
+//! ### Generating bindings in your crate
+//! The [protocol!] macro generates domain modules inline,
+//! optionally from your own protocol files, and for a subset of the domains
+//! (along with the domains they depend on):
+//! ```
+//! mod cdp {
+//!     chrome_devtools_api::protocol!(domains = [Runtime]);
+//! }
+//!
+//! use chrome_devtools_api::util::Command;
+//! assert_eq!(cdp::runtime::Evaluate::id(), "Runtime.evaluate");
+//! ```
+//! 
+//! ### JSON Schema
+//! The whole protocol is also available as a JSON Schema (draft 2020-12)
+//! document in [protocol::JSON_SCHEMA], for validating traffic outside of Rust.
+//! 
 
-#![feature(associated_type_defaults)]
 pub mod util;
 
+pub mod discovery;
+
+pub mod server;
+
+#[cfg(any(feature = "client", feature = "blocking"))]
+pub mod client;
+
+#[cfg(test)]
+mod test_util;
+
+pub use chrome_devtools_macros::protocol;
+
 #[allow(deprecated)]
 #[allow(clippy::enum_variant_names)]
+#[allow(clippy::large_enum_variant)]
 pub mod protocol;
 pub use util::*;
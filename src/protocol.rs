@@ -1 +1,17 @@
-include!(concat!(env!("OUT_DIR"), "/__protocol.rs"));
\ No newline at end of file
+include!(concat!(env!("OUT_DIR"), "/__protocol.rs"));
+
+///
+/// JSON Schema (draft 2020-12) document describing the entire protocol,
+/// generated from the same definitions as the types in this module.
+///
+/// Types are defined as `Domain.Type`, commands as `Domain.command.params`
+/// and `Domain.command.returns`, and events as `Domain.event.event`.
+///
+pub const JSON_SCHEMA: &str = include_str!(concat!(env!("OUT_DIR"), "/__protocol.schema.json"));
+
+///
+/// Definition of the entire protocol (`{ "version": ..., "domains": [...] }`),
+/// as served by browsers on `/json/protocol`, merged from the same files
+/// as the types in this module.
+///
+pub const PROTOCOL_JSON: &str = include_str!(concat!(env!("OUT_DIR"), "/__protocol.json"));
@@ -0,0 +1,320 @@
+//!
+//! Events sent by a server, routed to the sessions (and flattened sessions)
+//! which enabled their domain.
+//!
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use tokio::sync::mpsc;
+
+use crate::util::{Domain, Event, RawNotification, SessionId};
+
+///
+/// Where the events of a session go.
+///
+struct Outbox {
+    frames: mpsc::Sender<String>,
+
+    ///
+    /// Whether this is a flattened session within a connection,
+    /// whose events are sent with its `sessionId`.
+    ///
+    flat: bool,
+
+    ///
+    /// Domains enabled in this session (e.g. `Page`).
+    ///
+    enabled: HashSet<String>,
+}
+
+///
+/// Emits events to the sessions connected to a server, each with
+/// a bounded buffer of frames, and the domains it has enabled:
+/// ```
+/// use chrome_devtools_api::{protocol::page, server::Emitter};
+///
+/// let emitter = Emitter::new();
+/// let (session, mut frames) = emitter.connect();
+///
+/// // Events of a domain (with an `enable` command) are only sent once it's enabled.
+/// let load = page::LoadEventFiredEvent { timestamp: 1.0 };
+/// assert_eq!(emitter.emit(&load).unwrap(), 0);
+///
+/// emitter.enable(&session, "Page");
+/// assert_eq!(emitter.emit(&load).unwrap(), 1);
+///
+/// let frame = frames.try_recv().unwrap();
+/// assert_eq!(frame, r#"{"method":"Page.loadEventFired","params":{"timestamp":1.0}}"#);
+/// ```
+///
+/// A session which falls behind by more than its buffer's capacity
+/// is disconnected (along with the sessions flattened within its connection),
+/// rather than missing some of its events.
+///
+#[derive(Clone)]
+pub struct Emitter {
+    sessions: Arc<Mutex<HashMap<SessionId, Outbox>>>,
+    next_id: Arc<AtomicU64>,
+    capacity: usize,
+}
+
+impl Emitter {
+    pub fn new() -> Self {
+        Self::with_capacity(1024)
+    }
+
+    ///
+    /// Buffers up to `capacity` frames for each connection,
+    /// panicking if `capacity` is 0 (there must be room for one).
+    ///
+    pub fn with_capacity(capacity: usize) -> Self {
+        assert!(capacity > 0, "an emitter's capacity must be at least 1");
+
+        Self {
+            sessions: Default::default(),
+            next_id: Default::default(),
+            capacity,
+        }
+    }
+
+    ///
+    /// Registers a connection as a new session, returning its id,
+    /// and the frames to send to it.
+    ///
+    pub fn connect(&self) -> (SessionId, mpsc::Receiver<String>) {
+        let (frames, rx) = mpsc::channel(self.capacity);
+        let id = format!("{:032X}", self.next_id.fetch_add(1, Ordering::Relaxed));
+
+        let outbox = Outbox {
+            frames,
+            flat: false,
+            enabled: HashSet::new(),
+        };
+
+        self.sessions.lock().unwrap().insert(id.clone(), outbox);
+        (id, rx)
+    }
+
+    ///
+    /// Registers a flattened session `id` (e.g. attached to with `Target.attachToTarget`)
+    /// within the connection of session `parent`, returning whether `parent` exists.
+    ///
+    pub fn attach(&self, parent: &str, id: impl Into<SessionId>) -> bool {
+        let mut sessions = self.sessions.lock().unwrap();
+
+        let Some(frames) = sessions.get(parent).map(|outbox| outbox.frames.clone()) else {
+            return false;
+        };
+
+        let outbox = Outbox {
+            frames,
+            flat: true,
+            enabled: HashSet::new(),
+        };
+
+        sessions.insert(id.into(), outbox);
+        true
+    }
+
+    ///
+    /// Unregisters a session, along with the sessions flattened within it
+    /// if it's a connection (whose frames then end).
+    ///
+    pub fn detach(&self, id: &str) {
+        let mut sessions = self.sessions.lock().unwrap();
+
+        match sessions.remove(id) {
+            Some(outbox) if !outbox.flat => {
+                sessions.retain(|_, other| !other.frames.same_channel(&outbox.frames))
+            }
+            _ => {}
+        }
+    }
+
+    ///
+    /// Starts sending the events of `domain` (e.g. `Page`) to session `id`.
+    ///
+    pub fn enable(&self, id: &str, domain: &str) {
+        if let Some(outbox) = self.sessions.lock().unwrap().get_mut(id) {
+            outbox.enabled.insert(domain.to_string());
+        }
+    }
+
+    ///
+    /// Stops sending the events of `domain` to session `id`.
+    ///
+    pub fn disable(&self, id: &str, domain: &str) {
+        if let Some(outbox) = self.sessions.lock().unwrap().get_mut(id) {
+            outbox.enabled.remove(domain);
+        }
+    }
+
+    pub fn is_enabled(&self, id: &str, domain: &str) -> bool {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(id)
+            .is_some_and(|outbox| outbox.enabled.contains(domain))
+    }
+
+    ///
+    /// Sends `event` to every session which enabled its domain (or to every
+    /// session, if the domain has no `enable` command), returning to how many.
+    ///
+    pub fn emit<E: Event>(&self, event: &E) -> Result<usize, serde_json::Error> {
+        self.send::<E>(None, event)
+    }
+
+    ///
+    /// Sends `event` to session `id` only (if it enabled the event's domain),
+    /// returning whether it did.
+    ///
+    pub fn emit_to<E: Event>(&self, id: &str, event: &E) -> Result<bool, serde_json::Error> {
+        self.send::<E>(Some(id), event).map(|sent| sent > 0)
+    }
+
+    fn send<E: Event>(&self, to: Option<&str>, event: &E) -> Result<usize, serde_json::Error> {
+        let notification = RawNotification {
+            method: event.id().to_string(),
+            params: Some(serde_json::to_value(event)?),
+            session_id: None,
+        };
+
+        // Serialized once, outside the lock: flattened sessions only append their id.
+        let frame = serde_json::to_string(&notification)?;
+        let domain = event.id().split_once('.').map_or("", |(domain, _)| domain);
+        let always = E::Domain::enable().is_none();
+
+        let mut sessions = self.sessions.lock().unwrap();
+        let mut sent = 0;
+        let mut lagging = Vec::new();
+
+        for (id, outbox) in sessions.iter() {
+            if to.is_some_and(|to| to != id) || !(always || outbox.enabled.contains(domain)) {
+                continue;
+            }
+
+            let frame = match outbox.flat {
+                true => with_session_id(&frame, id),
+                false => frame.clone(),
+            };
+
+            match outbox.frames.try_send(frame) {
+                Ok(()) => sent += 1,
+                Err(_) => lagging.push(outbox.frames.clone()),
+            }
+        }
+
+        // Full or closed: either way, the connection is done for.
+        for frames in lagging {
+            sessions.retain(|_, outbox| !outbox.frames.same_channel(&frames));
+        }
+
+        Ok(sent)
+    }
+}
+
+///
+/// `frame` (a serialized [RawNotification] without a `sessionId`),
+/// with `sessionId` set to `id`.
+///
+fn with_session_id(frame: &str, id: &str) -> String {
+    let object = frame.strip_suffix('}').expect("notifications are objects");
+    format!("{object},\"sessionId\":{}}}", serde_json::Value::from(id))
+}
+
+impl Default for Emitter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::protocol::{page, target};
+
+    #[test]
+    #[should_panic(expected = "capacity must be at least 1")]
+    fn test_zero_capacity() {
+        Emitter::with_capacity(0);
+    }
+
+    #[test]
+    fn test_emitter() {
+        let emitter = Emitter::with_capacity(2);
+
+        let (first, mut first_frames) = emitter.connect();
+        let (second, mut second_frames) = emitter.connect();
+        assert!(emitter.attach(&first, "flat"));
+        assert!(!emitter.attach("unknown", "other"));
+
+        let frame = |frames: &mut mpsc::Receiver<String>| {
+            serde_json::from_str::<Value>(&frames.try_recv().unwrap()).unwrap()
+        };
+
+        // No `Target.enable`: sent to every session.
+        let destroyed = target::TargetDestroyedEvent {
+            target_id: "1".to_string(),
+        };
+        assert_eq!(emitter.emit(&destroyed).unwrap(), 3);
+
+        // Once for the connection, and once for the session flattened within it.
+        let sent = [frame(&mut first_frames), frame(&mut first_frames)];
+        assert!(sent.iter().all(|sent| sent["params"]["targetId"] == "1"));
+        assert!(sent.iter().any(|sent| sent.get("sessionId").is_none()));
+        assert!(sent.iter().any(|sent| sent["sessionId"] == "flat"));
+
+        assert_eq!(
+            frame(&mut second_frames)["method"],
+            "Target.targetDestroyed"
+        );
+
+        let load = page::LoadEventFiredEvent { timestamp: 1.0 };
+        emitter.enable(&second, "Page");
+        emitter.enable("flat", "Page");
+
+        assert!(emitter.is_enabled(&second, "Page"));
+        assert!(!emitter.is_enabled(&first, "Page"));
+        assert!(!emitter.emit_to(&first, &load).unwrap());
+        assert!(emitter.emit_to("flat", &load).unwrap());
+        assert_eq!(
+            first_frames.try_recv().unwrap(),
+            r#"{"method":"Page.loadEventFired","params":{"timestamp":1.0},"sessionId":"flat"}"#
+        );
+
+        emitter.disable("flat", "Page");
+        assert_eq!(emitter.emit(&load).unwrap(), 1);
+        assert_eq!(frame(&mut second_frames)["method"], "Page.loadEventFired");
+
+        // Falling behind disconnects the whole connection.
+        assert_eq!(emitter.emit(&destroyed).unwrap(), 3);
+        frame(&mut second_frames);
+
+        assert_eq!(emitter.emit(&destroyed).unwrap(), 1);
+        assert!(!emitter.emit_to("flat", &destroyed).unwrap());
+
+        frame(&mut first_frames);
+        frame(&mut first_frames);
+        assert_eq!(
+            first_frames.try_recv(),
+            Err(mpsc::error::TryRecvError::Disconnected)
+        );
+
+        emitter.detach(&second);
+        assert_eq!(emitter.emit(&destroyed).unwrap(), 0);
+        frame(&mut second_frames);
+        assert_eq!(
+            second_frames.try_recv(),
+            Err(mpsc::error::TryRecvError::Disconnected)
+        );
+    }
+}
@@ -0,0 +1,636 @@
+use std::{collections::HashMap, future::Future, io, net::SocketAddr, sync::Arc};
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::{
+    io::{
+        AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt,
+        BufReader,
+    },
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::mpsc,
+};
+use tokio_tungstenite::{
+    tungstenite::{handshake::derive_accept_key, protocol::Role, Message as WsMessage},
+    WebSocketStream,
+};
+
+use super::{Emitter, Router};
+use crate::{
+    discovery::{BrowserVersion, TargetDescription},
+    protocol::PROTOCOL_JSON,
+    util::SessionId,
+};
+
+///
+/// Longest line of an HTTP request (or of its headers) accepted, in bytes.
+///
+const MAX_LINE: u64 = 8 * 1024;
+
+///
+/// Most headers accepted in an HTTP request.
+///
+const MAX_HEADERS: usize = 100;
+
+///
+/// Handler of the frames of a WebSocket connection to a target,
+/// returning the frame to send back (if any).
+///
+/// Implemented by a [Router], along with the context of its connection
+/// (tracking the domains enabled by the connection's sessions).
+///
+pub trait MessageHandler: Send + 'static {
+    fn handle(&mut self, frame: &str) -> impl Future<Output = Option<String>> + Send;
+
+    ///
+    /// Called before handling any frame, with the connection's session of `emitter`.
+    ///
+    fn connected(&mut self, emitter: &Emitter, session: &SessionId) {
+        let _ = (emitter, session);
+    }
+}
+
+impl<Ctx: Send + 'static> MessageHandler for (Router<Ctx>, Ctx) {
+    async fn handle(&mut self, frame: &str) -> Option<String> {
+        let (router, ctx) = self;
+        router.handle(ctx, frame).await
+    }
+
+    fn connected(&mut self, emitter: &Emitter, session: &SessionId) {
+        self.0.track(emitter.clone(), session.clone());
+    }
+}
+
+///
+/// A server exposing targets to DevTools (or any other client),
+/// as browsers do with `--remote-debugging-port`:
+/// * `/json/version`: a [BrowserVersion] (see [Listener::version])
+/// * `/json/list` (or `/json`): a [TargetDescription] per target
+/// * `/json/protocol`: the protocol definition ([PROTOCOL_JSON])
+/// * `/devtools/page/{id}` (or `/devtools/browser/{id}`, for a `browser` target):
+///   the target's WebSocket, whose frames are handled by a [MessageHandler] of its own.
+///
+/// Each WebSocket connection is also a session of the listener's [Emitter]
+/// (see [Listener::emitter]), sending it the events emitted to it.
+///
+/// ```no_run
+/// use chrome_devtools_api::{discovery::TargetDescription, server::{Listener, Router}};
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let target = TargetDescription {
+///     id: "main".to_string(),
+///     type_: "node".to_string(),
+///     title: "My application".to_string(),
+///     url: "file:///app".to_string(),
+///     description: String::new(),
+///     devtools_frontend_url: None,
+///     favicon_url: None,
+///     parent_id: None,
+///     web_socket_debugger_url: None,
+/// };
+///
+/// // Prints `devtools://devtools/bundled/inspector.html?ws=127.0.0.1:9229/devtools/page/main`.
+/// Listener::bind("127.0.0.1:9229")
+///     .await?
+///     .target(target)
+///     .serve(|_, _| (Router::new(), ()))
+///     .await
+/// # }
+/// ```
+///
+pub struct Listener {
+    listener: TcpListener,
+    version: BrowserVersion,
+    targets: Vec<TargetDescription>,
+    emitter: Emitter,
+    quiet: bool,
+}
+
+impl Listener {
+    ///
+    /// Listens on `addr`, with no targets yet.
+    ///
+    pub async fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let product = format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+
+        let protocol = serde_json::from_str::<serde_json::Value>(PROTOCOL_JSON)?;
+        let version = |part: &str| {
+            protocol["version"][part]
+                .as_str()
+                .unwrap_or("0")
+                .to_string()
+        };
+
+        Ok(Self {
+            listener: TcpListener::bind(addr).await?,
+            version: BrowserVersion {
+                browser: product.clone(),
+                protocol_version: format!("{}.{}", version("major"), version("minor")),
+                user_agent: product,
+                v8_version: None,
+                webkit_version: None,
+                android_package: None,
+                web_socket_debugger_url: None,
+            },
+            targets: Vec::new(),
+            emitter: Emitter::new(),
+            quiet: false,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    ///
+    /// Serves `version` on `/json/version`, in place of this crate's name and
+    /// protocol version (its `webSocketDebuggerUrl` is the `browser` target's, if any).
+    ///
+    pub fn version(mut self, version: BrowserVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    ///
+    /// Adds a target, listed (with its `webSocketDebuggerUrl` and
+    /// `devtoolsFrontendUrl` filled in, if missing) and accepting connections.
+    ///
+    pub fn target(mut self, target: TargetDescription) -> Self {
+        self.targets.push(target);
+        self
+    }
+
+    ///
+    /// Registers connections with `emitter` (in place of one of the listener's own),
+    /// so events can be emitted to them.
+    ///
+    pub fn emitter(mut self, emitter: Emitter) -> Self {
+        self.emitter = emitter;
+        self
+    }
+
+    ///
+    /// Doesn't print the targets' URLs when serving.
+    ///
+    pub fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
+    }
+
+    ///
+    /// Accepts connections until failing to, with a handler made by `handlers`
+    /// for each WebSocket connection to a target (given its id as a session of the [Emitter]).
+    ///
+    /// Unless [quiet](Self::quiet), first prints the WebSocket URL of each target,
+    /// and the URL to inspect it with DevTools (`devtools://devtools/bundled/inspector.html?ws=...`).
+    ///
+    pub async fn serve<F, H>(self, handlers: F) -> io::Result<()>
+    where
+        F: Fn(&TargetDescription, &SessionId) -> H + Send + Sync + 'static,
+        H: MessageHandler,
+    {
+        let addr = self.listener.local_addr()?;
+
+        if !self.quiet {
+            for target in &self.targets {
+                let host = addr.to_string();
+
+                eprintln!("DevTools listening on ws://{host}{}", target_path(target));
+                eprintln!("Inspect with {}", inspector_url(&host, target));
+            }
+        }
+
+        let served = Arc::new(Served {
+            version: self.version,
+            targets: self.targets,
+            emitter: self.emitter,
+            handlers,
+        });
+
+        loop {
+            let (stream, _) = self.listener.accept().await?;
+            let served = served.clone();
+
+            tokio::spawn(async move {
+                // A failing connection only concerns its own client.
+                let _ = served.accept(stream, addr).await;
+            });
+        }
+    }
+}
+
+struct Served<F> {
+    version: BrowserVersion,
+    targets: Vec<TargetDescription>,
+    emitter: Emitter,
+    handlers: F,
+}
+
+impl<F, H> Served<F>
+where
+    F: Fn(&TargetDescription, &SessionId) -> H,
+    H: MessageHandler,
+{
+    ///
+    /// Serves an HTTP request, or upgrades it to a target's WebSocket.
+    ///
+    async fn accept(&self, stream: TcpStream, addr: SocketAddr) -> io::Result<()> {
+        let mut stream = BufReader::new(stream);
+
+        let mut line = String::new();
+
+        if !read_line(&mut stream, &mut line).await? {
+            return respond(
+                stream,
+                "400 Bad Request",
+                "text/plain",
+                "Request line too long",
+            )
+            .await;
+        }
+
+        let path = line.split(' ').nth(1).unwrap_or_default();
+        let path = path.split('?').next().unwrap_or_default().to_string();
+
+        let mut headers = HashMap::new();
+
+        for count in 0.. {
+            if !read_line(&mut stream, &mut line).await? {
+                return respond(stream, "400 Bad Request", "text/plain", "Header too long").await;
+            }
+
+            if line.trim_end().is_empty() {
+                break;
+            }
+
+            if count == MAX_HEADERS {
+                return respond(stream, "400 Bad Request", "text/plain", "Too many headers").await;
+            }
+
+            if let Some((name, value)) = line.split_once(':') {
+                headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+            }
+        }
+
+        // URLs are given relative to the host the client reached, as Chrome does.
+        let host = headers.remove("host").unwrap_or_else(|| addr.to_string());
+
+        if let Some(key) = headers.get("sec-websocket-key") {
+            return match self
+                .targets
+                .iter()
+                .find(|target| target_path(target) == path)
+            {
+                Some(target) => {
+                    let accept = derive_accept_key(key.trim().as_bytes());
+
+                    stream
+                        .write_all(
+                            format!(
+                                "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\
+                                Connection: Upgrade\r\nSec-WebSocket-Accept: {accept}\r\n\r\n"
+                            )
+                            .as_bytes(),
+                        )
+                        .await?;
+
+                    let socket = WebSocketStream::from_raw_socket(stream, Role::Server, None).await;
+                    let (session, events) = self.emitter.connect();
+
+                    let mut handler = (self.handlers)(target, &session);
+                    handler.connected(&self.emitter, &session);
+
+                    run(socket, handler, events).await;
+                    self.emitter.detach(&session);
+
+                    Ok(())
+                }
+                None => respond(stream, "404 Not Found", "text/plain", "No such target id").await,
+            };
+        }
+
+        let json = match path.trim_end_matches('/') {
+            "/json/version" => {
+                let mut version = self.version.clone();
+                version.web_socket_debugger_url = self
+                    .targets
+                    .iter()
+                    .find(|target| target.type_ == "browser")
+                    .map(|target| websocket_url(&host, target));
+
+                serde_json::to_string(&version)?
+            }
+            "/json" | "/json/list" => {
+                let targets = self
+                    .targets
+                    .iter()
+                    .map(|target| describe(&host, target))
+                    .collect::<Vec<_>>();
+
+                serde_json::to_string(&targets)?
+            }
+            "/json/protocol" => PROTOCOL_JSON.to_string(),
+            _ => {
+                let body = format!("Unknown url: {path}");
+                return respond(stream, "404 Not Found", "text/plain", &body).await;
+            }
+        };
+
+        respond(stream, "200 OK", "application/json; charset=UTF-8", &json).await
+    }
+}
+
+///
+/// Hands the text frames of `socket` to `handler` until it closes,
+/// sending back its responses, along with the `events` emitted to it
+/// (until it is disconnected from the [Emitter]).
+///
+async fn run<S, H>(
+    mut socket: WebSocketStream<S>,
+    mut handler: H,
+    mut events: mpsc::Receiver<String>,
+) where
+    S: AsyncRead + AsyncWrite + Unpin,
+    H: MessageHandler,
+{
+    'run: loop {
+        let frame = tokio::select! {
+            message = socket.next() => match message {
+                Some(Ok(WsMessage::Text(frame))) => {
+                    let response = handler.handle(&frame).await;
+
+                    // Events emitted while handling a call (e.g. when enabling
+                    // a domain) are sent before its response, as Chrome does.
+                    while let Ok(event) = events.try_recv() {
+                        if socket.send(WsMessage::Text(event)).await.is_err() {
+                            break 'run;
+                        }
+                    }
+
+                    response
+                }
+                Some(Ok(_)) => None,
+                Some(Err(_)) | None => break,
+            },
+            event = events.recv() => match event {
+                Some(event) => Some(event),
+                None => break,
+            },
+        };
+
+        if let Some(frame) = frame {
+            if socket.send(WsMessage::Text(frame)).await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+///
+/// Sends a response, and closes the connection.
+///
+async fn respond<S>(mut stream: S, status: &str, content_type: &str, body: &str) -> io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    let head = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+        Connection: close\r\n\r\n",
+        body.len()
+    );
+
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+///
+/// Reads a line of up to [MAX_LINE] bytes into `line`, returning whether
+/// it fit (the stream having ended if `line` is empty).
+///
+async fn read_line<R>(stream: &mut R, line: &mut String) -> io::Result<bool>
+where
+    R: AsyncBufRead + Unpin,
+{
+    line.clear();
+    stream.take(MAX_LINE).read_line(line).await?;
+
+    Ok(line.is_empty() || line.ends_with('\n'))
+}
+
+///
+/// Path of a target's WebSocket, as Chrome names them.
+///
+fn target_path(target: &TargetDescription) -> String {
+    match target.type_.as_str() {
+        "browser" => format!("/devtools/browser/{}", target.id),
+        _ => format!("/devtools/page/{}", target.id),
+    }
+}
+
+fn websocket_url(host: &str, target: &TargetDescription) -> String {
+    format!("ws://{host}{}", target_path(target))
+}
+
+///
+/// URL of DevTools (as bundled with Chrome) inspecting a target.
+///
+fn inspector_url(host: &str, target: &TargetDescription) -> String {
+    format!(
+        "devtools://devtools/bundled/inspector.html?ws={host}{}",
+        target_path(target)
+    )
+}
+
+fn describe(host: &str, target: &TargetDescription) -> TargetDescription {
+    TargetDescription {
+        web_socket_debugger_url: target
+            .web_socket_debugger_url
+            .clone()
+            .or_else(|| Some(websocket_url(host, target))),
+        devtools_frontend_url: target
+            .devtools_frontend_url
+            .clone()
+            .or_else(|| Some(inspector_url(host, target))),
+        ..target.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        discovery::Client,
+        protocol::{runtime, target},
+        util::ProtocolError,
+    };
+
+    struct Runtime;
+
+    impl runtime::RuntimeHandler<String> for Runtime {
+        async fn evaluate(
+            &mut self,
+            target: &mut String,
+            _: runtime::EvaluateParams,
+        ) -> Result<runtime::EvaluateReturns, ProtocolError> {
+            Ok(runtime::EvaluateReturns {
+                result: runtime::RemoteObject {
+                    value: Some(target.clone().into()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+        }
+    }
+
+    async fn receive<S>(socket: &mut WebSocketStream<S>) -> Value
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        match socket.next().await.unwrap().unwrap() {
+            WsMessage::Text(frame) => serde_json::from_str(&frame).unwrap(),
+            message => panic!("Unexpected message: {message:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_listener() {
+        let target = TargetDescription {
+            id: "main".to_string(),
+            type_: "node".to_string(),
+            title: "Test".to_string(),
+            url: "file:///test".to_string(),
+            description: String::new(),
+            devtools_frontend_url: None,
+            favicon_url: None,
+            parent_id: None,
+            web_socket_debugger_url: None,
+        };
+
+        let emitter = Emitter::new();
+        let listener = Listener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .target(target)
+            .emitter(emitter.clone())
+            .quiet();
+
+        let host = listener.local_addr().unwrap().to_string();
+
+        tokio::spawn(listener.serve(|target, _| {
+            let router = Router::new()
+                .route::<runtime::RuntimeDomain, _>(Runtime)
+                .on_enable::<runtime::RuntimeDomain>(
+                |target: &mut String, emitter, session| {
+                    let created = runtime::ExecutionContextCreatedEvent {
+                        context: runtime::ExecutionContextDescription {
+                            id: 1,
+                            name: target.clone(),
+                            ..Default::default()
+                        },
+                    };
+
+                    emitter.emit_to(session, &created).unwrap();
+                },
+            );
+
+            (router, target.id.clone())
+        }));
+
+        let client = Client::new(host.clone());
+        let (version, targets, protocol, unknown) = tokio::task::spawn_blocking(move || {
+            (
+                client.version().unwrap(),
+                client.list().unwrap(),
+                client.protocol().unwrap(),
+                client.activate("main"),
+            )
+        })
+        .await
+        .unwrap();
+
+        assert!(version.browser.starts_with("chrome-devtools-api/"));
+        assert_eq!(version.web_socket_debugger_url, None);
+        assert!(protocol["domains"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|domain| domain["domain"] == "Runtime"));
+        assert!(unknown.is_err());
+
+        let url = format!("ws://{host}/devtools/page/main");
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].web_socket_debugger_url, Some(url.clone()));
+        assert_eq!(
+            targets[0].devtools_frontend_url,
+            Some(format!(
+                "devtools://devtools/bundled/inspector.html?ws={host}/devtools/page/main"
+            ))
+        );
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+
+        let call =
+            json!({ "id": 1, "method": "Runtime.evaluate", "params": { "expression": "1" } });
+        socket
+            .send(WsMessage::Text(call.to_string()))
+            .await
+            .unwrap();
+
+        let response = receive(&mut socket).await;
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["result"]["value"], "main");
+
+        let destroyed = target::TargetDestroyedEvent {
+            target_id: "main".to_string(),
+        };
+        assert_eq!(emitter.emit(&destroyed).unwrap(), 1);
+
+        let event = receive(&mut socket).await;
+        assert_eq!(event["method"], "Target.targetDestroyed");
+        assert_eq!(event["params"]["targetId"], "main");
+
+        // Enabling a domain (not implemented by its handler) replays its events first.
+        let enable = json!({ "id": 2, "method": "Runtime.enable" });
+        socket
+            .send(WsMessage::Text(enable.to_string()))
+            .await
+            .unwrap();
+
+        let event = receive(&mut socket).await;
+        assert_eq!(event["method"], "Runtime.executionContextCreated");
+        assert_eq!(event["params"]["context"]["name"], "main");
+
+        let response = receive(&mut socket).await;
+        assert_eq!(response["id"], 2);
+        assert_eq!(response["result"], json!({}));
+
+        let unknown = format!("ws://{host}/devtools/page/unknown");
+        assert!(tokio_tungstenite::connect_async(unknown).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_limits() {
+        let listener = Listener::bind("127.0.0.1:0").await.unwrap().quiet();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(listener.serve(|_, _| (Router::<()>::new(), ())));
+
+        let long = format!("GET /{}", "a".repeat(MAX_LINE as usize - 5));
+        let many = format!(
+            "GET /json HTTP/1.1\r\n{}",
+            "X-Header: 1\r\n".repeat(MAX_HEADERS + 1)
+        );
+
+        for request in [long, many] {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream.write_all(request.as_bytes()).await.unwrap();
+
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+
+            assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        }
+    }
+}
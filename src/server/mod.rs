@@ -0,0 +1,27 @@
+//!
+//! # Server
+//! Building blocks for servers implementing the protocol (for a custom
+//! runtime, or to expose an application to Chrome DevTools):
+//! * Each domain's handler trait (e.g. [PageHandler](crate::protocol::page::PageHandler)),
+//!   with a method per command.
+//! * A [Router], parsing incoming frames and routing their calls
+//!   to the handler of their domain.
+//! * A [Listener] (with the `server` feature), serving targets over WebSocket
+//!   along with the `/json` endpoints DevTools discovers them with.
+//! * An [Emitter] (with the `server` feature), sending events to the sessions
+//!   which enabled their domain, as tracked by their [Router].
+//!
+
+#[cfg(feature = "server")]
+mod emitter;
+#[cfg(feature = "server")]
+mod listener;
+mod router;
+#[cfg(feature = "server")]
+mod tracker;
+
+#[cfg(feature = "server")]
+pub use emitter::*;
+#[cfg(feature = "server")]
+pub use listener::*;
+pub use router::*;
@@ -0,0 +1,449 @@
+//!
+//! Routing of incoming frames to the handlers of their domain, turning
+//! each call into the response to send back.
+//!
+
+use std::{collections::HashMap, marker::PhantomData};
+
+use serde_json::{json, Value};
+
+#[cfg(feature = "server")]
+use super::{tracker::Tracker, Emitter};
+use crate::util::{or_empty, CallFuture, Dispatch, ProtocolError, RawRequest, RawResponse};
+#[cfg(feature = "server")]
+use crate::util::{Domain, SessionId};
+
+///
+/// A handler of some domain, with the domain erased.
+///
+trait Route<Ctx>: Send {
+    fn handle<'a>(
+        &'a mut self,
+        ctx: &'a mut Ctx,
+        method: &str,
+        params: Value,
+    ) -> Option<CallFuture<'a, Value, ProtocolError>>;
+}
+
+struct Routed<D, H> {
+    handler: H,
+    _domain: PhantomData<fn() -> D>,
+}
+
+impl<D: Dispatch<H, Ctx>, Ctx, H: Send> Route<Ctx> for Routed<D, H> {
+    fn handle<'a>(
+        &'a mut self,
+        ctx: &'a mut Ctx,
+        method: &str,
+        params: Value,
+    ) -> Option<CallFuture<'a, Value, ProtocolError>> {
+        D::dispatch(&mut self.handler, ctx, method, params)
+    }
+}
+
+///
+/// Routes incoming calls to the handler of their domain,
+/// with some context `Ctx` of the server's own (e.g. per connection or session).
+///
+/// Calls fail with `-32601` ("method not found") if their domain has no handler,
+/// or their handler doesn't implement them, and with `-32602` ("invalid params")
+/// naming the offending field if their params can't be deserialized:
+/// ```
+/// use chrome_devtools_api::{protocol::page, server::Router, util::ProtocolError};
+/// use serde_json::{json, Value};
+///
+/// struct Page;
+///
+/// impl page::PageHandler<()> for Page {
+///     async fn navigate(
+///         &mut self,
+///         _: &mut (),
+///         params: page::NavigateParams,
+///     ) -> Result<page::NavigateReturns, ProtocolError> {
+///         Ok(page::NavigateReturns {
+///             frame_id: params.url,
+///             ..Default::default()
+///         })
+///     }
+/// }
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let mut router = Router::new().route::<page::PageDomain, _>(Page);
+///
+/// let call = json!({ "id": 1, "method": "Page.navigate", "params": { "url": "about:blank" } });
+/// let response = router.handle(&mut (), &call.to_string()).await.unwrap();
+/// let response = serde_json::from_str::<Value>(&response).unwrap();
+///
+/// assert_eq!(response["id"], 1);
+/// assert_eq!(response["result"]["frameId"], "about:blank");
+/// # });
+/// ```
+///
+/// With the `server` feature, a router can also [track](Self::track) the domains
+/// enabled by the sessions of an [Emitter], as they call their `enable` and `disable`
+/// commands (which then succeed even if their handler doesn't implement them).
+///
+pub struct Router<Ctx> {
+    domains: HashMap<&'static str, Box<dyn Route<Ctx>>>,
+
+    #[cfg(feature = "server")]
+    tracker: Tracker<Ctx>,
+}
+
+impl<Ctx> Router<Ctx> {
+    pub fn new() -> Self {
+        Self {
+            domains: HashMap::new(),
+
+            #[cfg(feature = "server")]
+            tracker: Tracker::new(),
+        }
+    }
+
+    ///
+    /// Routes the calls of domain `D` to `handler` (in place of any previous one).
+    ///
+    pub fn route<D, H>(mut self, handler: H) -> Self
+    where
+        D: Dispatch<H, Ctx> + 'static,
+        H: Send + 'static,
+    {
+        let route = Routed::<D, H> {
+            handler,
+            _domain: PhantomData,
+        };
+
+        #[cfg(feature = "server")]
+        self.tracker.route::<D>();
+
+        self.domains.insert(D::name(), Box::new(route));
+        self
+    }
+
+    ///
+    /// Calls `hook` whenever a (tracked) session enables domain `D`, before responding,
+    /// with the router's context, the emitter, and the session's id: for instance,
+    /// to emit `Runtime.executionContextCreated` for existing contexts, as Chrome does.
+    ///
+    #[cfg(feature = "server")]
+    pub fn on_enable<D: Domain>(
+        mut self,
+        hook: impl FnMut(&mut Ctx, &Emitter, &str) + Send + 'static,
+    ) -> Self {
+        self.tracker.on_enable(D::name(), Box::new(hook));
+        self
+    }
+
+    ///
+    /// Enables (and disables) the domains of the sessions of `emitter` as they
+    /// call the `enable` (and `disable`) commands of the routed domains, with
+    /// calls lacking a `sessionId` made by session `session` (a connection).
+    ///
+    /// The [Listener](super::Listener) does so for the router of each connection.
+    ///
+    #[cfg(feature = "server")]
+    pub fn track(&mut self, emitter: Emitter, session: SessionId) {
+        self.tracker.track(emitter, session);
+    }
+
+    ///
+    /// Calls the handler of `method` (e.g. `Page.navigate`) with raw parameters.
+    ///
+    pub async fn call(
+        &mut self,
+        ctx: &mut Ctx,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, ProtocolError> {
+        let call = method
+            .split_once('.')
+            .and_then(|(domain, _)| self.domains.get_mut(domain))
+            .and_then(|route| route.handle(ctx, method, params));
+
+        match call {
+            Some(call) => call.await,
+            None => Err(ProtocolError::method_not_found(method)),
+        }
+    }
+
+    ///
+    /// Calls the handler of `request`, returning its response.
+    ///
+    pub async fn request(&mut self, ctx: &mut Ctx, request: RawRequest) -> RawResponse {
+        let params = or_empty(request.params);
+
+        #[cfg(feature = "server")]
+        let toggle = self
+            .tracker
+            .toggle(&request.method, request.session_id.as_ref());
+
+        let result = self.call(ctx, &request.method, params).await;
+
+        #[cfg(feature = "server")]
+        let result = match toggle {
+            Some(toggle) => self.tracker.toggled(ctx, toggle, result),
+            None => result,
+        };
+
+        RawResponse {
+            id: request.id,
+            result: result.map_err(|error| json!(error)),
+            session_id: request.session_id,
+        }
+    }
+
+    ///
+    /// Handles an incoming frame, returning the frame to send back (if any):
+    /// * The response of a request.
+    /// * An error with no `id`, if the frame isn't valid JSON (`-32700`),
+    ///   or an error with the `id` of the frame (if any), if it isn't
+    ///   a valid request (`-32600`), as Chrome does.
+    /// * Nothing, for anything else (e.g. a response).
+    ///
+    pub async fn handle(&mut self, ctx: &mut Ctx, frame: &str) -> Option<String> {
+        let message = match serde_json::from_str::<Value>(frame) {
+            Ok(message) => message,
+            Err(error) => {
+                let error = ProtocolError::parse_error("Message must be a valid JSON")
+                    .with_data(error.to_string());
+
+                return Some(json!({ "error": error }).to_string());
+            }
+        };
+
+        // Only requests (with a method) are answered.
+        message.get("method")?;
+
+        let response = match serde_json::from_value::<RawRequest>(message.clone()) {
+            Ok(request) => json!(self.request(ctx, request).await),
+            Err(error) => {
+                let error = ProtocolError::invalid_request(
+                    "Message must have integer 'id' and string 'method' properties",
+                )
+                .with_data(error.to_string());
+
+                match message.get("id") {
+                    Some(id) => json!({ "id": id, "error": error }),
+                    None => json!({ "error": error }),
+                }
+            }
+        };
+
+        Some(response.to_string())
+    }
+}
+
+impl<Ctx> Default for Router<Ctx> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{protocol::runtime, util::ErrorCode};
+
+    struct Runtime;
+
+    impl runtime::RuntimeHandler<u32> for Runtime {
+        async fn evaluate(
+            &mut self,
+            calls: &mut u32,
+            params: runtime::EvaluateParams,
+        ) -> Result<runtime::EvaluateReturns, ProtocolError> {
+            *calls += 1;
+
+            Ok(runtime::EvaluateReturns {
+                result: runtime::RemoteObject {
+                    value: Some(params.expression.into()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+        }
+
+        async fn global_lexical_scope_names(
+            &mut self,
+            _: &mut u32,
+            params: runtime::GlobalLexicalScopeNamesParams,
+        ) -> Result<runtime::GlobalLexicalScopeNamesReturns, ProtocolError> {
+            Ok(runtime::GlobalLexicalScopeNamesReturns {
+                names: params
+                    .execution_context_id
+                    .into_iter()
+                    .map(|id| id.to_string())
+                    .collect(),
+            })
+        }
+    }
+
+    async fn handle(router: &mut Router<u32>, calls: &mut u32, frame: Value) -> Value {
+        let response = router.handle(calls, &frame.to_string()).await.unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    fn error(response: Value) -> ProtocolError {
+        serde_json::from_value(response["error"].clone()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_router() {
+        let mut router = Router::new().route::<runtime::RuntimeDomain, _>(Runtime);
+        let mut calls = 0;
+
+        let response = handle(
+            &mut router,
+            &mut calls,
+            json!({ "id": 1, "method": "Runtime.evaluate", "params": { "expression": "1" }, "sessionId": "S" }),
+        )
+        .await;
+
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["sessionId"], "S");
+        assert_eq!(response["result"]["result"]["value"], "1");
+        assert_eq!(calls, 1);
+
+        // Invalid params, naming the field.
+        let response = handle(
+            &mut router,
+            &mut calls,
+            json!({ "id": 2, "method": "Runtime.evaluate", "params": { "expression": 1 } }),
+        )
+        .await;
+
+        let invalid = error(response);
+        assert_eq!(invalid.code, ErrorCode::InvalidParams);
+        assert!(invalid
+            .data
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .starts_with("params.expression:"));
+
+        // Missing params.
+        let response = handle(
+            &mut router,
+            &mut calls,
+            json!({ "id": 3, "method": "Runtime.evaluate" }),
+        )
+        .await;
+        assert_eq!(error(response).code, ErrorCode::InvalidParams);
+
+        // Missing, but optional params.
+        let response = handle(
+            &mut router,
+            &mut calls,
+            json!({ "id": 3, "method": "Runtime.globalLexicalScopeNames" }),
+        )
+        .await;
+        assert_eq!(response["result"], json!({ "names": [] }));
+
+        // Not implemented, unknown, and without a handler.
+        for method in [
+            "Runtime.enable",
+            "Runtime.unknown",
+            "Page.enable",
+            "Unknown",
+        ] {
+            let response = handle(
+                &mut router,
+                &mut calls,
+                json!({ "id": 4, "method": method }),
+            )
+            .await;
+
+            assert_eq!(response["id"], 4);
+            assert_eq!(error(response), ProtocolError::method_not_found(method));
+        }
+
+        assert_eq!(calls, 1);
+
+        let response = router.handle(&mut calls, "{").await.unwrap();
+        let response = serde_json::from_str::<Value>(&response).unwrap();
+
+        assert_eq!(response.get("id"), None);
+        assert_eq!(error(response).code, ErrorCode::ParseError);
+
+        let response = handle(
+            &mut router,
+            &mut calls,
+            json!({ "id": "5", "method": "Runtime.enable" }),
+        )
+        .await;
+
+        assert_eq!(response["id"], "5");
+        assert_eq!(error(response).code, ErrorCode::InvalidRequest);
+
+        // Not a request.
+        let response = json!({ "id": 6, "result": {} }).to_string();
+        assert_eq!(router.handle(&mut calls, &response).await, None);
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_tracking() {
+        let emitter = Emitter::new();
+        let (session, _frames) = emitter.connect();
+        emitter.attach(&session, "flat");
+
+        let mut router = Router::new()
+            .route::<runtime::RuntimeDomain, _>(Runtime)
+            .on_enable::<runtime::RuntimeDomain>(|calls: &mut u32, _, _| *calls += 1);
+        let mut calls = 0;
+
+        // Untracked: the handler's (unimplemented) `enable` is called as is.
+        let response = handle(
+            &mut router,
+            &mut calls,
+            json!({ "id": 1, "method": "Runtime.enable" }),
+        )
+        .await;
+        assert_eq!(
+            error(response),
+            ProtocolError::method_not_found("Runtime.enable")
+        );
+        assert!(!emitter.is_enabled(&session, "Runtime"));
+
+        router.track(emitter.clone(), session.clone());
+
+        let response = handle(
+            &mut router,
+            &mut calls,
+            json!({ "id": 2, "method": "Runtime.enable" }),
+        )
+        .await;
+        assert_eq!(response["result"], json!({}));
+        assert!(emitter.is_enabled(&session, "Runtime"));
+        assert!(!emitter.is_enabled("flat", "Runtime"));
+        assert_eq!(calls, 1);
+
+        let enable = json!({ "id": 3, "method": "Runtime.enable", "sessionId": "flat" });
+        handle(&mut router, &mut calls, enable).await;
+        assert!(emitter.is_enabled("flat", "Runtime"));
+        assert_eq!(calls, 2);
+
+        let response = handle(
+            &mut router,
+            &mut calls,
+            json!({ "id": 4, "method": "Runtime.disable" }),
+        )
+        .await;
+        assert_eq!(response["result"], json!({}));
+        assert!(!emitter.is_enabled(&session, "Runtime"));
+        assert!(emitter.is_enabled("flat", "Runtime"));
+
+        // Domains without a handler aren't tracked.
+        let response = handle(
+            &mut router,
+            &mut calls,
+            json!({ "id": 5, "method": "Page.enable" }),
+        )
+        .await;
+        assert_eq!(
+            error(response),
+            ProtocolError::method_not_found("Page.enable")
+        );
+        assert!(!emitter.is_enabled(&session, "Page"));
+    }
+}
@@ -0,0 +1,127 @@
+//!
+//! Bookkeeping of the domains each session of a [Router](super::Router) enabled,
+//! so its [Emitter] only sends them the events they asked for.
+//!
+
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+use super::Emitter;
+use crate::util::{Domain, ErrorCode, ProtocolError, SessionId};
+
+///
+/// Hook called once a domain is enabled, with the router's context,
+/// the emitter, and the id of the session which enabled it.
+///
+pub(super) type Hook<Ctx> = Box<dyn FnMut(&mut Ctx, &Emitter, &str) + Send>;
+
+///
+/// A call to the `enable` (or `disable`) command of a domain.
+///
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Toggle {
+    domain: &'static str,
+    enable: bool,
+}
+
+///
+/// Enables (and disables) the domains of the sessions of an [Emitter],
+/// as they call their `enable` (and `disable`) commands.
+///
+pub(super) struct Tracker<Ctx> {
+    toggles: HashMap<&'static str, Toggle>,
+    hooks: HashMap<&'static str, Vec<Hook<Ctx>>>,
+    tracked: Option<(Emitter, SessionId)>,
+}
+
+impl<Ctx> Tracker<Ctx> {
+    pub(super) fn new() -> Self {
+        Self {
+            toggles: HashMap::new(),
+            hooks: HashMap::new(),
+            tracked: None,
+        }
+    }
+
+    ///
+    /// Tracks the `enable` and `disable` commands of `D`.
+    ///
+    pub(super) fn route<D: Domain>(&mut self) {
+        for (method, enable) in [(D::enable(), true), (D::disable(), false)] {
+            if let Some(method) = method {
+                let domain = D::name();
+                self.toggles.insert(method, Toggle { domain, enable });
+            }
+        }
+    }
+
+    pub(super) fn on_enable(&mut self, domain: &'static str, hook: Hook<Ctx>) {
+        self.hooks.entry(domain).or_default().push(hook);
+    }
+
+    ///
+    /// Tracks the sessions of `emitter`, with calls lacking a `sessionId`
+    /// made by session `session`.
+    ///
+    pub(super) fn track(&mut self, emitter: Emitter, session: SessionId) {
+        self.tracked = Some((emitter, session));
+    }
+
+    ///
+    /// The toggle `method` is (if tracked), along with the session calling it,
+    /// having enabled its domain right away if it's an `enable` command (so events
+    /// emitted while enabling it are sent), and whether it was enabled before.
+    ///
+    pub(super) fn toggle(
+        &self,
+        method: &str,
+        session_id: Option<&SessionId>,
+    ) -> Option<(Toggle, SessionId, bool)> {
+        let (emitter, session) = self.tracked.as_ref()?;
+        let toggle = *self.toggles.get(method)?;
+
+        let session = session_id.unwrap_or(session).clone();
+        let enabled = emitter.is_enabled(&session, toggle.domain);
+
+        if toggle.enable {
+            emitter.enable(&session, toggle.domain);
+        }
+
+        Some((toggle, session, enabled))
+    }
+
+    ///
+    /// Records the result of a toggle, treating the command as done if
+    /// the handler of the domain doesn't implement it, and calls the hooks
+    /// of the domain once it's enabled.
+    ///
+    pub(super) fn toggled(
+        &mut self,
+        ctx: &mut Ctx,
+        (toggle, session, enabled): (Toggle, SessionId, bool),
+        result: Result<Value, ProtocolError>,
+    ) -> Result<Value, ProtocolError> {
+        let Some((emitter, _)) = self.tracked.as_ref() else {
+            return result;
+        };
+
+        let result = match result {
+            Err(error) if error.code == ErrorCode::MethodNotFound => Ok(json!({})),
+            result => result,
+        };
+
+        match (&result, toggle.enable) {
+            (Ok(_), true) => {
+                for hook in self.hooks.get_mut(toggle.domain).into_iter().flatten() {
+                    hook(ctx, emitter, &session);
+                }
+            }
+            (Ok(_), false) => emitter.disable(&session, toggle.domain),
+            (Err(_), true) if !enabled => emitter.disable(&session, toggle.domain),
+            (Err(_), _) => {}
+        }
+
+        result
+    }
+}
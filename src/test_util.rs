@@ -0,0 +1,72 @@
+//!
+//! Fixtures shared by tests: servers standing in for a browser, each serving
+//! a single connection on a thread of its own (so that async and blocking
+//! clients alike can connect to them).
+//!
+
+use std::{
+    net::{SocketAddr, TcpListener, TcpStream},
+    thread,
+};
+
+///
+/// Accepts a single connection on a local port, handled by `handle`,
+/// returning the port's address and the handle's result.
+///
+pub fn accept<T, F>(handle: F) -> (SocketAddr, thread::JoinHandle<T>)
+where
+    T: Send + 'static,
+    F: FnOnce(TcpStream) -> T + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let handle = thread::spawn(move || handle(listener.accept().unwrap().0));
+    (addr, handle)
+}
+
+///
+/// Starts a WebSocket server sending back the frames `respond` returns
+/// for each request, until the client hangs up. Returns its URL.
+///
+#[cfg(feature = "client")]
+pub fn serve<F>(respond: F) -> String
+where
+    F: FnMut(serde_json::Value) -> Vec<serde_json::Value> + Send + 'static,
+{
+    serve_requests(usize::MAX, respond)
+}
+
+///
+/// Like [serve], but hangs up after `requests` requests.
+///
+#[cfg(any(feature = "client", feature = "blocking"))]
+pub fn serve_requests<F>(requests: usize, mut respond: F) -> String
+where
+    F: FnMut(serde_json::Value) -> Vec<serde_json::Value> + Send + 'static,
+{
+    use tungstenite::Message as WsMessage;
+
+    let (addr, _) = accept(move |stream| {
+        let mut socket = tungstenite::accept(stream).unwrap();
+
+        for _ in 0..requests {
+            let request = match socket.read() {
+                Ok(WsMessage::Text(frame)) => serde_json::from_str(&frame).unwrap(),
+                _ => return,
+            };
+
+            for frame in respond(request) {
+                if socket.send(WsMessage::Text(frame.to_string())).is_err() {
+                    return;
+                }
+            }
+        }
+
+        // Until the client acknowledges the close (or hangs up).
+        let _ = socket.close(None);
+        while socket.read().is_ok() {}
+    });
+
+    format!("ws://{addr}")
+}
@@ -0,0 +1,709 @@
+//!
+//! CBOR encoding of protocol messages, as used by Chrome's
+//! `--remote-debugging-pipe=cbor` mode and its internal protocol.
+//!
+//! Each message is a map wrapped in an *envelope*: a byte string tagged 24
+//! ("encoded CBOR data item") with a 32-bit length. Inside, maps and arrays
+//! have indefinite length, doubles are always 64 bits wide, and binary values
+//! ([Binary](super::Binary)) are byte strings tagged 22 ("expected base64").
+//!
+//! Values are encoded straight from their [Serialize] implementation, and
+//! decoded through [serde_json::Value] like the rest of the crate's messages,
+//! so any type (de)serializable as JSON is (de)serializable as CBOR, including
+//! the envelopes of [Message](super::Message):
+//! ```
+//! use chrome_devtools_api::{
+//!     protocol::page,
+//!     util::{cbor, Message, Request},
+//! };
+//!
+//! let request = Request::<page::Navigate>::new(1, page::NavigateParams {
+//!     url: "https://example.com".to_string(),
+//!     ..Default::default()
+//! });
+//!
+//! let bytes = cbor::encode(&request).unwrap();
+//!
+//! match cbor::decode::<Message>(&bytes).unwrap() {
+//!     Message::Request(raw) => assert_eq!(raw.parse::<page::Navigate>().unwrap().params.url, "https://example.com"),
+//!     _ => unreachable!(),
+//! }
+//! ```
+//!
+//! Byte strings are decoded into base64 strings, their JSON form, which
+//! [Binary](super::Binary) turns back into bytes.
+//!
+//! Values without an exact counterpart are rejected rather than rewritten:
+//! non-finite floats, integers a double can't hold exactly (Chrome only reads
+//! 32-bit integers, so larger ones are sent as doubles), non-string map keys,
+//! and tags other than binary values and embedded items.
+//!
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{de::DeserializeOwned, ser, Serialize};
+use serde_json::Value;
+
+///
+/// Tag 24, followed by the header of a byte string with a 32-bit length.
+///
+const ENVELOPE: [u8; 3] = [0xd8, 0x18, 0x5a];
+
+///
+/// Size of an envelope's header, length included.
+///
+pub const ENVELOPE_HEADER_LEN: usize = ENVELOPE.len() + 4;
+
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_TAG: u8 = 6;
+
+const TAG_BASE64: u64 = 22;
+const TAG_EMBEDDED: u64 = 24;
+
+const INDEFINITE_ARRAY: u8 = 0x9f;
+const INDEFINITE_MAP: u8 = 0xbf;
+const BREAK: u8 = 0xff;
+const FALSE: u8 = 0xf4;
+const TRUE: u8 = 0xf5;
+const NULL: u8 = 0xf6;
+const DOUBLE: u8 = 0xfb;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Expected a message wrapped in an envelope")]
+    InvalidEnvelope,
+
+    #[error("Message of {0} bytes does not fit in an envelope")]
+    TooLarge(usize),
+
+    #[error("Invalid CBOR: {0}")]
+    Decode(#[from] ciborium::de::Error<std::io::Error>),
+
+    #[error("Unsupported CBOR value: {0}")]
+    Unsupported(&'static str),
+
+    #[error("Unsupported CBOR tag {0}")]
+    Tag(u64),
+
+    #[error("{0}")]
+    Custom(String),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+impl ser::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self::Custom(msg.to_string())
+    }
+}
+
+///
+/// Encodes `value` as CBOR, wrapped in an envelope.
+///
+pub fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+    let mut payload = Writer(Vec::new());
+    value.serialize(&mut payload)?;
+
+    let Writer(payload) = payload;
+    let len = u32::try_from(payload.len()).map_err(|_| Error::TooLarge(payload.len()))?;
+
+    let mut bytes = Vec::with_capacity(ENVELOPE_HEADER_LEN + payload.len());
+    bytes.extend(ENVELOPE);
+    bytes.extend(len.to_be_bytes());
+    bytes.extend(payload);
+
+    Ok(bytes)
+}
+
+///
+/// Decodes a value from CBOR wrapped in an envelope.
+///
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Error> {
+    let payload = bytes
+        .get(ENVELOPE_HEADER_LEN..)
+        .filter(|payload| envelope_len(bytes) == Some(bytes.len()) && !payload.is_empty())
+        .ok_or(Error::InvalidEnvelope)?;
+
+    let value: ciborium::Value = ciborium::from_reader(payload)?;
+
+    Ok(serde_json::from_value(to_json(value)?)?)
+}
+
+///
+/// Total size of the envelope starting at `header`,
+/// which needs at least [ENVELOPE_HEADER_LEN] bytes.
+///
+/// Used to split a stream of bytes into messages.
+///
+pub fn envelope_len(header: &[u8]) -> Option<usize> {
+    match header.get(..ENVELOPE_HEADER_LEN)? {
+        [a, b, c, len @ ..] if [*a, *b, *c] == ENVELOPE => {
+            let len = u32::from_be_bytes(len.try_into().ok()?);
+            Some(ENVELOPE_HEADER_LEN + len as usize)
+        }
+        _ => None,
+    }
+}
+
+///
+/// Serializer writing Chrome's flavour of CBOR.
+///
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn header(&mut self, major: u8, n: u64) {
+        let major = major << 5;
+
+        match n {
+            0..=23 => self.0.push(major | n as u8),
+            24..=0xff => self.0.extend([major | 24, n as u8]),
+            0x100..=0xffff => {
+                self.0.push(major | 25);
+                self.0.extend((n as u16).to_be_bytes());
+            }
+            0x1_0000..=0xffff_ffff => {
+                self.0.push(major | 26);
+                self.0.extend((n as u32).to_be_bytes());
+            }
+            _ => {
+                self.0.push(major | 27);
+                self.0.extend(n.to_be_bytes());
+            }
+        }
+    }
+
+    fn text(&mut self, s: &str) {
+        self.header(MAJOR_TEXT, s.len() as u64);
+        self.0.extend(s.as_bytes());
+    }
+
+    fn integer(&mut self, n: i128) -> Result<(), Error> {
+        match i32::try_from(n) {
+            Ok(n) if n >= 0 => self.header(MAJOR_UNSIGNED, n as u64),
+            Ok(n) => self.header(MAJOR_NEGATIVE, (-1 - n as i64) as u64),
+            // Chrome only reads 32-bit integers, anything else is sent as a double.
+            Err(_) if n.unsigned_abs() <= 1 << f64::MANTISSA_DIGITS => {
+                return self.double(n as f64)
+            }
+            Err(_) => return Err(Error::Unsupported("integer too large for a double")),
+        }
+
+        Ok(())
+    }
+
+    fn double(&mut self, f: f64) -> Result<(), Error> {
+        if !f.is_finite() {
+            return Err(Error::Unsupported("non-finite float"));
+        }
+
+        self.0.push(DOUBLE);
+        self.0.extend(f.to_be_bytes());
+
+        Ok(())
+    }
+
+    fn compound(&mut self, start: u8, variant: Option<&str>) -> Compound<'_> {
+        if let Some(variant) = variant {
+            self.0.push(INDEFINITE_MAP);
+            self.text(variant);
+        }
+
+        self.0.push(start);
+
+        Compound {
+            writer: self,
+            variant: variant.is_some(),
+        }
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut Writer {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.0.push(if v { TRUE } else { FALSE });
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.integer(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.integer(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.integer(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        self.integer(v.into())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), Error> {
+        self.integer(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.integer(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.integer(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.integer(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        self.integer(v.into())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), Error> {
+        self.integer(v.try_into().unwrap_or(i128::MAX))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        self.double(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        self.double(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.text(v.encode_utf8(&mut [0; 4]));
+        Ok(())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.text(v);
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        self.header(MAJOR_TAG, TAG_BASE64);
+        self.header(MAJOR_BYTES, v.len() as u64);
+        self.0.extend(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.0.push(NULL);
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<(), Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.0.push(INDEFINITE_MAP);
+        self.text(variant);
+        value.serialize(&mut *self)?;
+        self.0.push(BREAK);
+        Ok(())
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Compound<'a>, Error> {
+        Ok(self.compound(INDEFINITE_ARRAY, None))
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Compound<'a>, Error> {
+        Ok(self.compound(INDEFINITE_ARRAY, None))
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Compound<'a>, Error> {
+        Ok(self.compound(INDEFINITE_ARRAY, None))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<Compound<'a>, Error> {
+        Ok(self.compound(INDEFINITE_ARRAY, Some(variant)))
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Compound<'a>, Error> {
+        Ok(self.compound(INDEFINITE_MAP, None))
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Compound<'a>, Error> {
+        Ok(self.compound(INDEFINITE_MAP, None))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<Compound<'a>, Error> {
+        Ok(self.compound(INDEFINITE_MAP, Some(variant)))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+///
+/// An indefinite-length array or map being written,
+/// itself inside a map if it's the content of an enum variant.
+///
+struct Compound<'a> {
+    writer: &'a mut Writer,
+    variant: bool,
+}
+
+impl Compound<'_> {
+    fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut *self.writer)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.writer.0.push(BREAK);
+
+        if self.variant {
+            self.writer.0.push(BREAK);
+        }
+
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeTuple for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeMap for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+        let start = self.writer.0.len();
+        self.element(key)?;
+
+        match self.writer.0[start] >> 5 {
+            MAJOR_TEXT => Ok(()),
+            _ => Err(Error::Unsupported("non-string map key")),
+        }
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeStruct for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.writer.text(key);
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeStructVariant for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.writer.text(key);
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Compound::end(self)
+    }
+}
+
+fn to_json(value: ciborium::Value) -> Result<Value, Error> {
+    use ciborium::Value as Cbor;
+
+    Ok(match value {
+        Cbor::Null => Value::Null,
+        Cbor::Bool(b) => Value::Bool(b),
+        Cbor::Integer(n) => {
+            let n = i128::from(n);
+
+            i64::try_from(n)
+                .map(Value::from)
+                .or_else(|_| u64::try_from(n).map(Value::from))
+                .map_err(|_| Error::Unsupported("integer out of range"))?
+        }
+        Cbor::Float(f) => serde_json::Number::from_f64(f)
+            .map(Value::Number)
+            .ok_or(Error::Unsupported("non-finite float"))?,
+        Cbor::Text(s) => Value::String(s),
+        Cbor::Bytes(b) => Value::String(BASE64.encode(b)),
+        Cbor::Tag(TAG_BASE64, value) => match *value {
+            Cbor::Bytes(b) => Value::String(BASE64.encode(b)),
+            _ => return Err(Error::Unsupported("base64 tag on a value other than bytes")),
+        },
+        Cbor::Tag(TAG_EMBEDDED, value) => match *value {
+            Cbor::Bytes(b) => to_json(ciborium::from_reader(&b[..])?)?,
+            _ => {
+                return Err(Error::Unsupported(
+                    "embedded item tag on a value other than bytes",
+                ))
+            }
+        },
+        Cbor::Tag(tag, _) => return Err(Error::Tag(tag)),
+        Cbor::Array(items) => items
+            .into_iter()
+            .map(to_json)
+            .collect::<Result<_, _>>()
+            .map(Value::Array)?,
+        Cbor::Map(entries) => entries
+            .into_iter()
+            .map(|(key, value)| match key {
+                Cbor::Text(key) => Ok((key, to_json(value)?)),
+                _ => Err(Error::Unsupported("non-string map key")),
+            })
+            .collect::<Result<_, _>>()
+            .map(Value::Object)?,
+        _ => return Err(Error::Unsupported("unknown value")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+    use crate::util::{Binary, Message, Nothing};
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Data {
+        data: Binary,
+    }
+
+    fn envelope(payload: &[u8]) -> Vec<u8> {
+        let mut bytes = ENVELOPE.to_vec();
+        bytes.extend((payload.len() as u32).to_be_bytes());
+        bytes.extend(payload);
+        bytes
+    }
+
+    #[test]
+    fn test_envelope() {
+        let bytes = encode(&json!({ "id": 1, "method": "Page.enable" })).unwrap();
+
+        assert_eq!(bytes[..3], ENVELOPE);
+        assert_eq!(envelope_len(&bytes), Some(bytes.len()));
+        assert_eq!(bytes[ENVELOPE_HEADER_LEN], INDEFINITE_MAP);
+        assert_eq!(bytes.last(), Some(&BREAK));
+
+        assert!(decode::<Message>(&bytes[..bytes.len() - 1]).is_err());
+        assert!(decode::<Message>(&bytes[ENVELOPE_HEADER_LEN..]).is_err());
+    }
+
+    #[test]
+    fn test_numbers() {
+        let value = json!([1, -24, 1.5, 1u64 << 40]);
+        let bytes = encode(&value).unwrap();
+
+        assert_eq!(
+            bytes[ENVELOPE_HEADER_LEN..ENVELOPE_HEADER_LEN + 4],
+            [INDEFINITE_ARRAY, 0x01, 0x37, DOUBLE]
+        );
+        assert_eq!(
+            decode::<Value>(&bytes).unwrap(),
+            json!([1, -24, 1.5, (1u64 << 40) as f64])
+        );
+
+        // Integers are decoded as they are, whatever their size.
+        let payload = [0x1b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+        assert_eq!(
+            decode::<Value>(&envelope(&payload)).unwrap(),
+            json!(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_unrepresentable() {
+        assert!(matches!(
+            encode(&((1u64 << 53) + 1)),
+            Err(Error::Unsupported(_))
+        ));
+        assert!(matches!(encode(&f64::NAN), Err(Error::Unsupported(_))));
+        assert!(matches!(encode(&f64::INFINITY), Err(Error::Unsupported(_))));
+        assert!(matches!(
+            encode(&BTreeMap::from([(1, 1)])),
+            Err(Error::Unsupported(_))
+        ));
+
+        let mut payload = vec![DOUBLE];
+        payload.extend(f64::NAN.to_be_bytes());
+        assert!(matches!(
+            decode::<Value>(&envelope(&payload)),
+            Err(Error::Unsupported(_))
+        ));
+
+        // `1(0)`, a date.
+        assert!(matches!(
+            decode::<Value>(&envelope(&[0xc1, 0x00])),
+            Err(Error::Tag(1))
+        ));
+    }
+
+    #[test]
+    fn test_binary() {
+        // `{ "data": 22(h'010203') }`, as sent by Chrome for binary values.
+        let bytes = envelope(&[
+            0xbf, 0x64, b'd', b'a', b't', b'a', 0xd6, 0x43, 1, 2, 3, BREAK,
+        ]);
+
+        assert_eq!(decode::<Value>(&bytes).unwrap(), json!({ "data": "AQID" }));
+        assert_eq!(decode::<Data>(&bytes).unwrap().data, Binary(vec![1, 2, 3]));
+
+        let data = Data {
+            data: Binary(vec![1, 2, 3]),
+        };
+
+        assert_eq!(encode(&data).unwrap(), bytes);
+        assert_eq!(
+            serde_json::to_value(&data).unwrap(),
+            json!({ "data": "AQID" })
+        );
+    }
+
+    #[test]
+    fn test_embedded() {
+        // `{ "a": 24(h'BF616201FF') }`: a nested envelope, around `{ "b": 1 }`.
+        let payload = [
+            0xbf, 0x61, b'a', 0xd8, 0x18, 0x45, 0xbf, 0x61, b'b', 0x01, BREAK, BREAK,
+        ];
+
+        assert_eq!(
+            decode::<Value>(&envelope(&payload)).unwrap(),
+            json!({ "a": { "b": 1 } })
+        );
+    }
+
+    #[test]
+    fn test_nothing() {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&Nothing, &mut bytes).unwrap();
+
+        assert_eq!(bytes, [0xa0]);
+        assert!(ciborium::from_reader::<Nothing, _>(&bytes[..]).is_ok());
+    }
+}
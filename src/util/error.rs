@@ -0,0 +1,191 @@
+//!
+//! Errors sent over the wire in place of a command's result:
+//! `{ "code": -32000, "message": "...", "data": ... }`.
+//!
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+///
+/// JSON-RPC error code of a [ProtocolError].
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    ///
+    /// Invalid JSON was received (`-32700`).
+    ///
+    ParseError,
+
+    ///
+    /// The message is not a valid request (`-32600`).
+    ///
+    InvalidRequest,
+
+    ///
+    /// The method does not exist, or is not available (`-32601`).
+    ///
+    MethodNotFound,
+
+    ///
+    /// Invalid method parameters (`-32602`).
+    ///
+    InvalidParams,
+
+    ///
+    /// Internal error (`-32603`).
+    ///
+    InternalError,
+
+    ///
+    /// Generic server error (`-32000`), used by Chrome
+    /// for most failures (e.g. "No node with given id found").
+    ///
+    ServerError,
+
+    ///
+    /// Any other code.
+    ///
+    Other(i64),
+}
+
+impl ErrorCode {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    pub const INTERNAL_ERROR: i64 = -32603;
+    pub const SERVER_ERROR: i64 = -32000;
+
+    ///
+    /// Numeric value of this code.
+    ///
+    pub fn code(self) -> i64 {
+        use ErrorCode::*;
+
+        match self {
+            ParseError => Self::PARSE_ERROR,
+            InvalidRequest => Self::INVALID_REQUEST,
+            MethodNotFound => Self::METHOD_NOT_FOUND,
+            InvalidParams => Self::INVALID_PARAMS,
+            InternalError => Self::INTERNAL_ERROR,
+            ServerError => Self::SERVER_ERROR,
+            Other(code) => code,
+        }
+    }
+}
+
+impl From<i64> for ErrorCode {
+    fn from(code: i64) -> Self {
+        use ErrorCode::*;
+
+        match code {
+            Self::PARSE_ERROR => ParseError,
+            Self::INVALID_REQUEST => InvalidRequest,
+            Self::METHOD_NOT_FOUND => MethodNotFound,
+            Self::INVALID_PARAMS => InvalidParams,
+            Self::INTERNAL_ERROR => InternalError,
+            Self::SERVER_ERROR => ServerError,
+            code => Other(code),
+        }
+    }
+}
+
+impl From<ErrorCode> for i64 {
+    fn from(code: ErrorCode) -> Self {
+        code.code()
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_i64(self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for ErrorCode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        i64::deserialize(deserializer).map(Self::from)
+    }
+}
+
+///
+/// Error returned in place of a command's result,
+/// by the browser, or by a server implementing the protocol.
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtocolError {
+    pub code: ErrorCode,
+    pub message: String,
+
+    ///
+    /// Optional extra information (usually a string).
+    ///
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl ProtocolError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    ///
+    /// Attaches extra information to this error.
+    ///
+    pub fn with_data(mut self, data: impl Into<serde_json::Value>) -> Self {
+        self.data = Some(data.into());
+        self
+    }
+
+    pub fn parse_error(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::ParseError, message)
+    }
+
+    pub fn invalid_request(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidRequest, message)
+    }
+
+    ///
+    /// `'{method}' wasn't found`, as reported by Chrome.
+    ///
+    pub fn method_not_found(method: &str) -> Self {
+        Self::new(ErrorCode::MethodNotFound, format!("'{method}' wasn't found"))
+    }
+
+    pub fn invalid_params(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidParams, message)
+    }
+
+    pub fn internal_error(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InternalError, message)
+    }
+
+    pub fn server_error(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::ServerError, message)
+    }
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code.code())?;
+
+        match self.data {
+            Some(serde_json::Value::String(ref data)) => write!(f, ": {data}"),
+            Some(ref data) => write!(f, ": {data}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
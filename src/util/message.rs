@@ -0,0 +1,659 @@
+//!
+//! Envelopes for the messages sent over the wire:
+//! * [Request]: `{ "id", "method", "params", "sessionId" }`
+//! * [Response]: `{ "id", "result" | "error", "sessionId" }`
+//! * [Notification]: `{ "method", "params", "sessionId" }`
+//!
+//! Each comes in a typed flavour (parameterized over a [Command] or [Event]),
+//! and a raw one (with undeserialized `params`), and [Message] classifies
+//! any incoming frame as one of the raw envelopes.
+//!
+
+use std::fmt;
+
+use serde::{de::Error, ser::SerializeMap, Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{Command, Event, Method};
+
+///
+/// Identifier of a [Request], echoed back in its [Response].
+///
+pub type CallId = u64;
+
+///
+/// Identifier of a (flattened) target session.
+///
+pub type SessionId = String;
+
+///
+/// Any frame on the wire, before classification.
+///
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Frame {
+    id: Option<CallId>,
+    method: Option<String>,
+    params: Option<Value>,
+
+    // A `null` result is still a result.
+    #[serde(default, deserialize_with = "present")]
+    result: Option<Value>,
+
+    error: Option<Value>,
+    session_id: Option<SessionId>,
+}
+
+///
+/// Deserializes a field which is present (even if `null`) as `Some`.
+///
+fn present<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
+}
+
+///
+/// A command call, with its raw `params`.
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawRequest {
+    pub id: CallId,
+    pub method: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<SessionId>,
+}
+
+///
+/// The result of a command call, with its raw `result` or `error`.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawResponse {
+    pub id: CallId,
+    pub result: Result<Value, Value>,
+    pub session_id: Option<SessionId>,
+}
+
+///
+/// An event, with its raw `params`.
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawNotification {
+    pub method: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<SessionId>,
+}
+
+///
+/// Any incoming frame, classified by its fields:
+/// * `id` and `method`: a [RawRequest].
+/// * `id` only: a [RawResponse].
+/// * `method` only: a [RawNotification].
+///
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Request(RawRequest),
+    Response(RawResponse),
+    Notification(RawNotification),
+}
+
+///
+/// A typed command call.
+///
+pub struct Request<C: Command> {
+    pub id: CallId,
+    pub params: C::Parameters,
+    pub session_id: Option<SessionId>,
+}
+
+///
+/// A typed command result.
+///
+pub struct Response<C: Command> {
+    pub id: CallId,
+    pub result: Result<C::Returns, C::Error>,
+    pub session_id: Option<SessionId>,
+}
+
+///
+/// A typed event.
+///
+#[derive(Debug, Clone)]
+pub struct Notification<E: Event> {
+    pub params: E,
+    pub session_id: Option<SessionId>,
+}
+
+///
+/// (Possibly missing) `params`, treating them as an empty object if missing.
+///
+pub(crate) fn or_empty(params: Option<Value>) -> Value {
+    params.unwrap_or_else(|| Value::Object(Default::default()))
+}
+
+///
+/// Deserialize (possibly missing) `params`, treating them as an empty object if missing.
+///
+fn params<T: serde::de::DeserializeOwned>(params: Option<Value>) -> Result<T, serde_json::Error> {
+    serde_json::from_value(or_empty(params))
+}
+
+///
+/// Ensures a raw method name matches the expected one.
+///
+fn expect_method(expected: &str, found: &str) -> Result<(), serde_json::Error> {
+    match expected == found {
+        true => Ok(()),
+        false => Err(serde_json::Error::custom(format!(
+            "Expected method `{expected}`, found `{found}`"
+        ))),
+    }
+}
+
+impl RawRequest {
+    ///
+    /// Deserialize into the typed [Request] of a specific command.
+    ///
+    pub fn parse<C: Command>(self) -> Result<Request<C>, serde_json::Error> {
+        expect_method(C::id(), &self.method)?;
+
+        Ok(Request {
+            id: self.id,
+            params: params(self.params)?,
+            session_id: self.session_id,
+        })
+    }
+
+    ///
+    /// Deserialize the method and its parameters into a [Method] enum,
+    /// such as [MethodCall](crate::protocol::MethodCall).
+    ///
+    pub fn into_method<M: Method>(self) -> Result<M, serde_json::Error> {
+        M::from_method(&self.method, or_empty(self.params))
+    }
+}
+
+impl RawResponse {
+    ///
+    /// Deserialize into the typed [Response] of a specific command.
+    ///
+    pub fn parse<C: Command>(self) -> Result<Response<C>, serde_json::Error> {
+        Ok(Response {
+            id: self.id,
+            result: match self.result {
+                Ok(result) => Ok(serde_json::from_value(result)?),
+                Err(error) => Err(serde_json::from_value(error)?),
+            },
+            session_id: self.session_id,
+        })
+    }
+}
+
+impl RawNotification {
+    ///
+    /// Deserialize into the typed [Notification] of a specific event.
+    ///
+    pub fn parse<E: Event>(self) -> Result<Notification<E>, serde_json::Error> {
+        expect_method(E::__id(), &self.method)?;
+
+        Ok(Notification {
+            params: params(self.params)?,
+            session_id: self.session_id,
+        })
+    }
+
+    ///
+    /// Deserialize the event into a [Method] enum,
+    /// such as [AnyEvent](crate::protocol::AnyEvent).
+    ///
+    pub fn into_method<M: Method>(self) -> Result<M, serde_json::Error> {
+        M::from_method(&self.method, or_empty(self.params))
+    }
+}
+
+impl Message {
+    ///
+    /// Session this message belongs to, if any.
+    ///
+    pub fn session_id(&self) -> Option<&str> {
+        match self {
+            Message::Request(r) => r.session_id.as_deref(),
+            Message::Response(r) => r.session_id.as_deref(),
+            Message::Notification(n) => n.session_id.as_deref(),
+        }
+    }
+}
+
+impl<C: Command> Request<C> {
+    pub fn new(id: CallId, params: C::Parameters) -> Self {
+        Self {
+            id,
+            params,
+            session_id: None,
+        }
+    }
+
+    ///
+    /// Sends this request to a (flattened) session.
+    ///
+    pub fn with_session(mut self, session_id: impl Into<SessionId>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+}
+
+impl<E: Event> Notification<E> {
+    pub fn new(params: E) -> Self {
+        Self {
+            params,
+            session_id: None,
+        }
+    }
+}
+
+impl<C: Command> TryFrom<RawRequest> for Request<C> {
+    type Error = serde_json::Error;
+
+    fn try_from(raw: RawRequest) -> Result<Self, Self::Error> {
+        raw.parse()
+    }
+}
+
+impl<C: Command> TryFrom<RawResponse> for Response<C> {
+    type Error = serde_json::Error;
+
+    fn try_from(raw: RawResponse) -> Result<Self, Self::Error> {
+        raw.parse()
+    }
+}
+
+impl<E: Event> TryFrom<RawNotification> for Notification<E> {
+    type Error = serde_json::Error;
+
+    fn try_from(raw: RawNotification) -> Result<Self, Self::Error> {
+        raw.parse()
+    }
+}
+
+///
+/// Serializes a `{ "id", "method", "params", "sessionId" }` object
+/// (requests and notifications).
+///
+fn serialize_call<S, P>(
+    serializer: S,
+    id: Option<CallId>,
+    method: &str,
+    params: &P,
+    session_id: &Option<SessionId>,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    P: Serialize,
+{
+    let mut map = serializer.serialize_map(None)?;
+
+    if let Some(id) = id {
+        map.serialize_entry("id", &id)?;
+    }
+
+    map.serialize_entry("method", method)?;
+    map.serialize_entry("params", params)?;
+
+    if let Some(session_id) = session_id {
+        map.serialize_entry("sessionId", session_id)?;
+    }
+
+    map.end()
+}
+
+///
+/// Serializes a `{ "id", "result" | "error", "sessionId" }` object.
+///
+fn serialize_response<S, R, E>(
+    serializer: S,
+    id: CallId,
+    result: Result<&R, &E>,
+    session_id: &Option<SessionId>,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    R: Serialize,
+    E: Serialize,
+{
+    let mut map = serializer.serialize_map(None)?;
+    map.serialize_entry("id", &id)?;
+
+    match result {
+        Ok(result) => map.serialize_entry("result", result)?,
+        Err(error) => map.serialize_entry("error", error)?,
+    }
+
+    if let Some(session_id) = session_id {
+        map.serialize_entry("sessionId", session_id)?;
+    }
+
+    map.end()
+}
+
+impl Serialize for RawResponse {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serialize_response(serializer, self.id, self.result.as_ref(), &self.session_id)
+    }
+}
+
+impl<'de> Deserialize<'de> for RawResponse {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match Message::deserialize(deserializer)? {
+            Message::Response(response) => Ok(response),
+            _ => Err(D::Error::custom("Expected a response")),
+        }
+    }
+}
+
+impl Serialize for Message {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Message::Request(request) => request.serialize(serializer),
+            Message::Response(response) => response.serialize(serializer),
+            Message::Notification(notification) => notification.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Message {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let Frame {
+            id,
+            method,
+            params,
+            result,
+            error,
+            session_id,
+        } = Frame::deserialize(deserializer)?;
+
+        match (id, method) {
+            (Some(id), Some(method)) => Ok(Message::Request(RawRequest {
+                id,
+                method,
+                params,
+                session_id,
+            })),
+            (Some(id), None) => Ok(Message::Response(RawResponse {
+                id,
+                result: match (result, error) {
+                    (_, Some(error)) => Err(error),
+                    (Some(result), None) => Ok(result),
+                    (None, None) => return Err(D::Error::missing_field("result")),
+                },
+                session_id,
+            })),
+            (None, Some(method)) => Ok(Message::Notification(RawNotification {
+                method,
+                params,
+                session_id,
+            })),
+            (None, None) => Err(D::Error::custom(
+                "Message has neither an `id`, nor a `method`",
+            )),
+        }
+    }
+}
+
+impl<C: Command> Serialize for Request<C> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serialize_call(
+            serializer,
+            Some(self.id),
+            C::id(),
+            &self.params,
+            &self.session_id,
+        )
+    }
+}
+
+impl<'de, C: Command> Deserialize<'de> for Request<C> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        RawRequest::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+impl<C: Command> Serialize for Response<C> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serialize_response(serializer, self.id, self.result.as_ref(), &self.session_id)
+    }
+}
+
+impl<'de, C: Command> Deserialize<'de> for Response<C> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        RawResponse::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+impl<E: Event> Serialize for Notification<E> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serialize_call(
+            serializer,
+            None,
+            self.params.id(),
+            &self.params,
+            &self.session_id,
+        )
+    }
+}
+
+impl<'de, E: Event> Deserialize<'de> for Notification<E> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        RawNotification::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+impl<C: Command> fmt::Debug for Request<C>
+where
+    C::Parameters: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Request")
+            .field("id", &self.id)
+            .field("method", &C::id())
+            .field("params", &self.params)
+            .field("session_id", &self.session_id)
+            .finish()
+    }
+}
+
+impl<C: Command> fmt::Debug for Response<C>
+where
+    C::Returns: fmt::Debug,
+    C::Error: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Response")
+            .field("id", &self.id)
+            .field("result", &self.result)
+            .field("session_id", &self.session_id)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{Message, RawRequest, Request, Response};
+    use crate::{
+        protocol::{page, tracing, AnyEvent, MethodCall},
+        util::{ErrorCode, Nothing, ProtocolError},
+    };
+
+    #[test]
+    fn test_classify() {
+        let frames = [
+            json!({ "id": 1, "method": "Page.enable", "sessionId": "A" }),
+            json!({ "id": 1, "result": {} }),
+            json!({ "id": 2, "error": { "code": -32601, "message": "Not found" } }),
+            json!({ "method": "Page.loadEventFired", "params": { "timestamp": 1.0 } }),
+        ];
+
+        let messages = frames
+            .iter()
+            .cloned()
+            .map(serde_json::from_value::<Message>)
+            .collect::<Result<Vec<_>, _>>()
+            .expect("valid frames");
+
+        assert!(matches!(&messages[0], Message::Request(r) if r.session_id.as_deref() == Some("A")));
+        assert!(matches!(&messages[1], Message::Response(r) if r.result.is_ok()));
+        assert!(matches!(&messages[2], Message::Response(r) if r.result.is_err()));
+        assert!(matches!(&messages[3], Message::Notification(_)));
+
+        for (frame, message) in frames.iter().zip(messages) {
+            assert_eq!(&serde_json::to_value(message).unwrap(), frame);
+        }
+
+        assert!(serde_json::from_value::<Message>(json!({ "params": {} })).is_err());
+    }
+
+    #[test]
+    fn test_error_response() {
+        let raw = json!({
+            "id": 5,
+            "error": { "code": -32000, "message": "Cannot navigate to invalid URL" }
+        });
+
+        let response = serde_json::from_value::<Response<page::Navigate>>(raw.clone()).unwrap();
+        let error = response.result.expect_err("error response");
+
+        assert_eq!(error.code, ErrorCode::ServerError);
+        assert_eq!(error.message, "Cannot navigate to invalid URL");
+
+        let response = Response::<page::Navigate> {
+            id: 5,
+            result: Err(ProtocolError::server_error("Cannot navigate to invalid URL")),
+            session_id: None,
+        };
+
+        assert_eq!(serde_json::to_value(&response).unwrap(), raw);
+    }
+
+    #[test]
+    fn test_missing_params() {
+        for params in [None, Some(json!(null)), Some(json!({}))] {
+            let raw = RawRequest {
+                id: 3,
+                method: "Page.enable".to_string(),
+                params,
+                session_id: None,
+            };
+
+            let request = raw.clone().parse::<page::Enable>().expect("valid request");
+            assert!(matches!(request.params, Nothing));
+            assert!(matches!(raw.into_method().unwrap(), MethodCall::Page_Enable(_)));
+        }
+
+        let request = Request::<page::Navigate>::new(
+            4,
+            page::NavigateParams {
+                url: "about:blank".to_string(),
+                ..Default::default()
+            },
+        )
+        .with_session("B");
+
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(value["method"], "Page.navigate");
+        assert_eq!(value["sessionId"], "B");
+
+        assert!(serde_json::from_value::<Request<page::Enable>>(value).is_err());
+    }
+
+    #[test]
+    fn test_null_result() {
+        let frame = json!({ "id": 1, "result": null });
+
+        let Message::Response(response) = serde_json::from_value(frame).unwrap() else {
+            panic!("Expected a response");
+        };
+
+        assert_eq!(response.result, Ok(json!(null)));
+        assert!(response.parse::<page::Enable>().unwrap().result.is_ok());
+
+        assert!(serde_json::from_value::<Message>(json!({ "id": 1 })).is_err());
+    }
+
+    #[test]
+    fn test_omitted_params() {
+        // Every parameter is optional, so `params` may be omitted altogether.
+        let frame = json!({ "id": 1, "method": "Page.captureScreenshot" });
+
+        let Message::Request(request) = serde_json::from_value(frame).unwrap() else {
+            panic!("Expected a request");
+        };
+
+        let typed = request.clone().parse::<page::CaptureScreenshot>().unwrap();
+        assert!(typed.params.format.is_none());
+        assert!(matches!(
+            request.into_method().unwrap(),
+            MethodCall::Page_CaptureScreenshot(_)
+        ));
+
+        let frame = json!({ "method": "Tracing.bufferUsage" });
+
+        let Message::Notification(notification) = serde_json::from_value(frame).unwrap() else {
+            panic!("Expected a notification");
+        };
+
+        let typed = notification
+            .clone()
+            .parse::<tracing::BufferUsageEvent>()
+            .unwrap();
+        assert!(typed.params.percent_full.is_none());
+        assert!(matches!(
+            notification.into_method().unwrap(),
+            AnyEvent::Tracing_BufferUsage(_)
+        ));
+    }
+}
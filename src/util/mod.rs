@@ -0,0 +1,467 @@
+use std::{fmt, future::Future, ops::Deref, pin::Pin};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{
+    de::{DeserializeOwned, Error, IgnoredAny, MapAccess, Visitor},
+    ser::SerializeMap,
+    Deserialize, Serialize,
+};
+
+#[cfg(feature = "cbor")]
+pub mod cbor;
+mod error;
+mod message;
+pub use error::*;
+pub use message::*;
+
+///
+/// Value of the protocol's `any` type.
+///
+/// Untyped values (these, and the raw parameters and results of messages)
+/// are kept as JSON whatever the encoding on the wire: the `cbor` feature
+/// converts CBOR messages to and from JSON values.
+///
+pub type Any = serde_json::Value;
+
+///
+/// Binary data, such as a screenshot: the protocol's strings
+/// "encoded as a base64 string when passed over JSON".
+///
+/// Serialized as a base64 string by human-readable formats (such as JSON),
+/// and as a byte string by the others (such as CBOR).
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Binary(pub Vec<u8>);
+
+impl From<Vec<u8>> for Binary {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<Binary> for Vec<u8> {
+    fn from(binary: Binary) -> Self {
+        binary.0
+    }
+}
+
+impl Deref for Binary {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Serialize for Binary {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&BASE64.encode(&self.0))
+        } else {
+            serializer.serialize_bytes(&self.0)
+        }
+    }
+}
+
+struct BinaryVisitor;
+
+impl<'de> Visitor<'de> for BinaryVisitor {
+    type Value = Binary;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a base64 string, or bytes")
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+        BASE64.decode(v).map(Binary).map_err(E::custom)
+    }
+
+    fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(Binary(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(Binary(v))
+    }
+}
+
+impl<'de> Deserialize<'de> for Binary {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(BinaryVisitor)
+        } else {
+            deserializer.deserialize_byte_buf(BinaryVisitor)
+        }
+    }
+}
+
+///
+/// Any event sent by the server to the client.
+///
+/// Events can be both serialized (by a server) and deserialized (by a client),
+/// and [AnyEvent](crate::protocol::AnyEvent) covers all of them at once.
+/// 
+pub trait Event : Serialize + DeserializeOwned {
+    ///
+    /// Marker type of the domain emitting this event.
+    ///
+    type Domain: Domain;
+
+    fn __id() -> &'static str
+    where Self: Sized;
+    
+    fn id(&self) -> &'static str;
+}
+
+///
+/// A protocol domain, implemented by the marker type
+/// generated in each domain's module (e.g. [PageDomain](crate::protocol::page::PageDomain)).
+///
+/// Every [Command] and [Event] links back to its domain,
+/// so code generic over either can find out which domain
+/// it belongs to, and how to enable it:
+/// ```
+/// use chrome_devtools_api::{protocol::page, util::{Command, Domain}};
+///
+/// fn enable_method<C: Command>() -> Option<&'static str> {
+///     C::Domain::enable()
+/// }
+///
+/// assert_eq!(enable_method::<page::Navigate>(), Some("Page.enable"));
+/// assert_eq!(page::PageDomain::name(), "Page");
+/// assert!(page::PageDomain::dependencies().contains(&"Network"));
+/// ```
+///
+pub trait Domain {
+    ///
+    /// Wire name of the domain (e.g. `Page`).
+    ///
+    fn name() -> &'static str;
+
+    ///
+    /// Wire names of the domains this domain depends on.
+    ///
+    fn dependencies() -> &'static [&'static str];
+
+    ///
+    /// Whether the domain is experimental.
+    ///
+    fn experimental() -> bool;
+
+    ///
+    /// Method name of the domain's `enable` command, if it has one.
+    ///
+    fn enable() -> Option<&'static str>;
+
+    ///
+    /// Method name of the domain's `disable` command, if it has one.
+    ///
+    fn disable() -> Option<&'static str>;
+}
+
+///
+/// Enum tagged by a method name on the wire, such as
+/// [MethodCall](crate::protocol::MethodCall) or [AnyEvent](crate::protocol::AnyEvent),
+/// which is (de)serialized
+/// as `{ "method": "...", "params": { ... } }`.
+///
+pub trait Method: Serialize + DeserializeOwned {
+    ///
+    /// Method name of this value (e.g. `Page.navigate`).
+    ///
+    fn method(&self) -> &str;
+
+    ///
+    /// Deserialize the `params` of the variant named `method`.
+    ///
+    fn from_method(method: &str, params: serde_json::Value) -> Result<Self, serde_json::Error>;
+}
+
+///
+/// A method name, with its (still undeserialized) parameters.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawMethod {
+    pub method: String,
+
+    #[serde(default)]
+    pub params: Option<serde_json::Value>,
+}
+
+///
+/// Serializes a `{ "method": "...", "params": { ... } }` pair,
+/// used by the generated [Method] implementations.
+///
+pub fn serialize_method<S, P>(serializer: S, method: &str, params: &P) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    P: Serialize,
+{
+    let mut map = serializer.serialize_map(Some(2))?;
+    map.serialize_entry("method", method)?;
+    map.serialize_entry("params", params)?;
+    map.end()
+}
+
+///
+/// Deserializes a [Method] from a `{ "method": "...", "params": { ... } }` pair,
+/// used by the generated [Method] implementations.
+///
+/// Missing `params` are treated as an empty object.
+///
+pub fn deserialize_method<'de, D, M>(deserializer: D) -> Result<M, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    M: Method,
+{
+    let RawMethod { method, params } = RawMethod::deserialize(deserializer)?;
+    let params = params.unwrap_or_else(|| serde_json::Value::Object(Default::default()));
+
+    M::from_method(&method, params).map_err(D::Error::custom)
+}
+
+///
+/// Links a Command to its Parameter and Return types.
+///
+/// All the associated types can be both serialized and deserialized,
+/// so the same trait can be used by a client (sending [Command::Parameters],
+/// receiving [Command::Returns]) and by a server (the other way around):
+/// ```
+/// use chrome_devtools_api::{protocol::page, util::Command};
+/// use serde_json::{json, Value};
+///
+/// fn send<C: Command>(id: u64, params: C::Parameters) -> Value {
+///     json!({ "id": id, "method": C::id(), "params": params })
+/// }
+///
+/// fn handle<C: Command>(raw: Value) -> C::Parameters {
+///     serde_json::from_value(raw["params"].clone()).unwrap()
+/// }
+///
+/// let params = page::NavigateParams {
+///     url: "https://example.com".to_string(),
+///     ..Default::default()
+/// };
+///
+/// let raw = send::<page::Navigate>(1, params);
+/// assert_eq!(handle::<page::Navigate>(raw).url, "https://example.com");
+/// ```
+///
+/// Commands fail with a [ProtocolError] (the generated commands' [Command::Error]).
+///  
+pub trait Command {
+    type Parameters: Serialize + DeserializeOwned;
+    type Returns: Serialize + DeserializeOwned;
+    type Error: Serialize + DeserializeOwned;
+
+    ///
+    /// Marker type of the domain this command belongs to.
+    ///
+    type Domain: Domain;
+
+    fn id() -> &'static str
+        where Self: Sized;
+}
+
+///
+/// Anything commands can be called through, such as a client's connection.
+///
+/// Each domain's generated `{Domain}Client` facade wraps a caller,
+/// with a method per command:
+/// ```no_run
+/// # #[cfg(feature = "client")]
+/// # async fn run(connection: chrome_devtools_api::client::Connection) -> Result<(), chrome_devtools_api::client::Error> {
+/// use chrome_devtools_api::protocol::page::PageClient;
+///
+/// let page = PageClient::new(&connection);
+///
+/// page.enable().await?;
+/// page.navigate("https://example.com").referrer("https://example.org").await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+pub trait Caller: Sync {
+    type Error: From<serde_json::Error> + Send;
+
+    ///
+    /// Calls a command by its method name, with raw parameters.
+    ///
+    fn call_raw(
+        &self,
+        method: &'static str,
+        params: serde_json::Value,
+    ) -> impl Future<Output = Result<serde_json::Value, Self::Error>> + Send;
+
+    ///
+    /// Calls the command `C`, and waits for its result.
+    ///
+    fn call<C: Command>(
+        &self,
+        params: C::Parameters,
+    ) -> impl Future<Output = Result<C::Returns, Self::Error>> + Send {
+        // Serialized right away, so the future doesn't hold on to `params`.
+        let params = serde_json::to_value(params);
+
+        async move {
+            let result = self.call_raw(C::id(), params?).await?;
+            Ok(serde_json::from_value(result)?)
+        }
+    }
+}
+
+///
+/// Future of a command called through a [Caller].
+///
+pub type CallFuture<'a, T, E> = Pin<Box<dyn Future<Output = Result<T, E>> + Send + 'a>>;
+
+///
+/// Calls to a domain's commands by method name, dispatched to a server's
+/// handler `H` of the domain, with some context `Ctx` of the server's own.
+///
+/// Implemented by each domain's marker type (e.g. [PageDomain](crate::protocol::page::PageDomain)),
+/// for every implementation of the domain's generated handler trait
+/// (e.g. [PageHandler](crate::protocol::page::PageHandler)),
+/// so a [Router](crate::server::Router) can route calls to it.
+///
+pub trait Dispatch<H, Ctx>: Domain {
+    ///
+    /// Calls the command named `method` (e.g. `Page.navigate`) of `handler` with raw
+    /// parameters, or returns `None` if it isn't one of the domain's commands.
+    ///
+    fn dispatch<'a>(
+        handler: &'a mut H,
+        ctx: &'a mut Ctx,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Option<CallFuture<'a, serde_json::Value, ProtocolError>>;
+}
+
+///
+/// Deserializes the raw parameters of the command `C` (with missing
+/// parameters treated as an empty object), failing with `-32602`
+/// ("invalid params") naming the offending field:
+/// ```
+/// use chrome_devtools_api::{protocol::page, util::{parse_params, ErrorCode}};
+/// use serde_json::json;
+///
+/// let error = parse_params::<page::Navigate>(json!({ "url": 1 })).unwrap_err();
+///
+/// assert_eq!(error.code, ErrorCode::InvalidParams);
+/// assert!(error.data.unwrap().as_str().unwrap().starts_with("params.url: invalid type"));
+/// ```
+///
+pub fn parse_params<C: Command>(params: serde_json::Value) -> Result<C::Parameters, ProtocolError> {
+    let params = match params {
+        serde_json::Value::Null => serde_json::Value::Object(Default::default()),
+        params => params,
+    };
+
+    serde_path_to_error::deserialize(params).map_err(|error| {
+        let field = match error.path().to_string() {
+            path if path == "." => "params".to_string(),
+            path => format!("params.{path}"),
+        };
+
+        ProtocolError::invalid_params("Invalid parameters")
+            .with_data(format!("{field}: {}", error.inner()))
+    })
+}
+
+///
+/// Serializes the returns of a command, failing with `-32603` ("internal error").
+///
+pub fn to_result<T: Serialize>(returns: T) -> Result<serde_json::Value, ProtocolError> {
+    serde_json::to_value(returns).map_err(|error| ProtocolError::internal_error(error.to_string()))
+}
+
+///
+/// No value: serialized as an empty object, and deserialized
+/// from an empty object, `null`, or a missing field.
+///
+#[derive(Debug, Clone, Default)]
+pub struct Nothing;
+
+struct NothingVisitor;
+
+impl<'de> Visitor<'de> for NothingVisitor {
+    type Value = Nothing;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an empty object, or null")
+    }
+
+    fn visit_none<E: Error>(self) -> Result<Self::Value, E> {
+        Ok(Nothing)
+    }
+
+    fn visit_unit<E: Error>(self) -> Result<Self::Value, E> {
+        Ok(Nothing)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        match map.next_key::<IgnoredAny>()? {
+            None => Ok(Nothing),
+            Some(_) => Err(A::Error::custom("Nothing struct has content!")),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Nothing {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_option(NothingVisitor)
+    }
+}
+
+impl Serialize for Nothing {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_map(Some(0))?.end()
+    }
+}
+
+///
+/// No possible value: it can never be constructed,
+/// so never serialized nor deserialized.
+///
+#[derive(Debug, Clone)]
+pub enum Infallible {}
+
+impl Serialize for Infallible {
+    fn serialize<S>(&self, _: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match *self {}
+    }
+}
+
+impl<'de> Deserialize<'de> for Infallible {
+    fn deserialize<D>(_: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Err(D::Error::custom("Infallible cannot be deserialized!"))
+    }
+}
\ No newline at end of file